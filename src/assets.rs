@@ -0,0 +1,3 @@
+pub mod collider_cache;
+pub mod ship_definition;
+pub mod thruster_mounts;
@@ -0,0 +1,247 @@
+use crate::components::defer_collider_loader::ConvexDecomposition;
+use bevy::{
+    asset::{io::file::FileAssetReader, AssetPath},
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use bevy_rapier3d::{
+    parry::{
+        math::{Point, Real},
+        transformation::vhacd::VHACD,
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Bumped whenever the cache file layout or the way it is generated changes.
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Resource, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct ColliderCache {
+    pub root: PathBuf,
+}
+
+impl Default for ColliderCache {
+    fn default() -> Self {
+        Self {
+            root: FileAssetReader::get_base_path().join("assets"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ColliderCacheError {
+    #[error("Could not access collider cache: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not parse collider cache: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Mesh is not a triangle list with positions")]
+    UnsupportedMesh,
+    #[error("Convex decomposition has no valid parts")]
+    InvalidParts,
+}
+
+type Triangles = (Vec<[f32; 3]>, Vec<[u32; 3]>);
+
+#[derive(Serialize, Deserialize)]
+struct ConvexPart {
+    vertices: Vec<[f32; 3]>,
+    indices: Vec<[u32; 3]>,
+}
+
+impl ColliderCache {
+    pub fn path(&self, asset_path: &AssetPath, key: u64) -> PathBuf {
+        self.root.join(asset_path.path()).with_file_name(format!(
+            "{}.{key:016x}.collider.json",
+            entry_name(asset_path)
+        ))
+    }
+
+    pub fn convex_decomposition(
+        &self,
+        asset_path: &AssetPath,
+        mesh: &Mesh,
+        decomposition: &ConvexDecomposition,
+    ) -> Result<Collider, ColliderCacheError> {
+        let (vertices, indices) =
+            mesh_triangles(mesh).ok_or(ColliderCacheError::UnsupportedMesh)?;
+        let path = self.path(asset_path, cache_key(&vertices, &indices, decomposition));
+
+        if path.exists() {
+            match read_parts(&path).and_then(compound) {
+                Ok((collider, _)) => return Ok(collider),
+                Err(err) => warn!("Regenerating {}: {err}", path.display()),
+            }
+        }
+
+        remove_stale(asset_path, &path);
+
+        info!("Computing convex decomposition of {asset_path}");
+        let parts = decompose(&vertices, &indices, decomposition);
+
+        let (collider, parts) = match compound(parts) {
+            Ok(compound) => compound,
+            Err(err) => {
+                remove_entry(&path);
+                return Err(err);
+            }
+        };
+
+        if let Err(err) = write_parts(&path, &parts) {
+            warn!("Could not write {}: {err}", path.display());
+            remove_entry(&path);
+        }
+
+        Ok(collider)
+    }
+}
+
+fn entry_name(asset_path: &AssetPath) -> String {
+    let stem = asset_path
+        .path()
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+
+    match asset_path.label() {
+        Some(label) => format!("{stem}.{}", label.replace(['/', '.'], "-")),
+        None => stem,
+    }
+}
+
+fn remove_stale(asset_path: &AssetPath, current: &Path) {
+    let Some(Ok(entries)) = current.parent().map(fs::read_dir) else {
+        return;
+    };
+
+    let prefix = format!("{}.", entry_name(asset_path));
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_entry = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix(&prefix))
+            .and_then(|name| name.strip_suffix(".collider.json"))
+            .is_some_and(|key| key.len() == 16 && u64::from_str_radix(key, 16).is_ok());
+
+        if is_entry && path != current {
+            info!("Removing stale {}", path.display());
+            remove_entry(&path);
+        }
+    }
+}
+
+fn mesh_triangles(mesh: &Mesh) -> Option<Triangles> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return None;
+    }
+
+    let vertices = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)?
+        .as_float3()?
+        .to_vec();
+    let indices: Vec<u32> = match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.iter().map(|&i| i as u32).collect(),
+        Some(Indices::U32(indices)) => indices.clone(),
+        None => (0..vertices.len() as u32).collect(),
+    };
+
+    let triangles = indices
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect();
+
+    Some((vertices, triangles))
+}
+
+/// FNV-1a over the mesh and the parameters, which unlike `DefaultHasher` stays the same
+/// between compiler versions.
+fn cache_key(
+    vertices: &[[f32; 3]],
+    indices: &[[u32; 3]],
+    decomposition: &ConvexDecomposition,
+) -> u64 {
+    let words = [
+        CACHE_VERSION,
+        decomposition.resolution,
+        decomposition.concavity.to_bits(),
+        decomposition.max_convex_hulls,
+    ]
+    .into_iter()
+    .chain(vertices.iter().flatten().map(|value| value.to_bits()))
+    .chain(indices.iter().flatten().copied());
+
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in words.flat_map(u32::to_le_bytes) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+fn decompose(
+    vertices: &[[f32; 3]],
+    indices: &[[u32; 3]],
+    decomposition: &ConvexDecomposition,
+) -> Vec<ConvexPart> {
+    let parameters = decomposition.parameters();
+    let points: Vec<Point<Real>> = vertices
+        .iter()
+        .map(|&[x, y, z]| Point::new(x, y, z))
+        .collect();
+
+    VHACD::decompose(&parameters, &points, indices, true)
+        .compute_exact_convex_hulls(&points, indices)
+        .into_iter()
+        .map(|(points, indices)| ConvexPart {
+            vertices: points
+                .iter()
+                .map(|point| [point.x, point.y, point.z])
+                .collect(),
+            indices,
+        })
+        .collect()
+}
+
+fn read_parts(path: &Path) -> Result<Vec<ConvexPart>, ColliderCacheError> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+fn write_parts(path: &Path, parts: &[ConvexPart]) -> Result<(), ColliderCacheError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut writer, parts)?;
+    Ok(writer.flush()?)
+}
+
+fn remove_entry(path: &Path) {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            warn!("Could not remove {}: {err}", path.display());
+        }
+        _ => {}
+    }
+}
+
+// Degenerate hulls are left out like parry does, only the parts that make it are kept
+fn compound(parts: Vec<ConvexPart>) -> Result<(Collider, Vec<ConvexPart>), ColliderCacheError> {
+    let (shapes, parts): (Vec<_>, Vec<_>) = parts
+        .into_iter()
+        .filter_map(|part| {
+            let vertices = part.vertices.iter().copied().map(Vec3::from).collect();
+            let collider = Collider::convex_mesh(vertices, &part.indices)?;
+            Some(((Vec3::ZERO, Quat::IDENTITY, collider), part))
+        })
+        .unzip();
+
+    if shapes.is_empty() {
+        return Err(ColliderCacheError::InvalidParts);
+    }
+
+    Ok((Collider::compound(shapes), parts))
+}
@@ -0,0 +1,314 @@
+use super::thruster_mounts::thrusters_from_gltf;
+use crate::components::{
+    control_authority::ControlAuthority,
+    defer_collider_loader::{DeferColliderLoader, MeshColliderShape},
+    fuel_tanks::FuelTanks,
+    gltf_collider_loader::GltfColliderLoader,
+    orientation_regulator::OrientationRegulator,
+    position_regulator::PositionRegulator,
+    thruster_classifier::ThrusterClassifier,
+    thruster_failures::ScheduledFailures,
+    thrusters::{Thruster, Thrusters},
+    velocity_regulator::VelocityRegulator,
+    weapons::{Hardpoint, Weapons},
+};
+use bevy::{
+    asset::{
+        io::Reader,
+        ron::{self, error::SpannedError},
+        AssetLoader, AsyncReadExt, LoadContext,
+    },
+    ecs::system::EntityCommands,
+    gltf::{Gltf, GltfNode},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+use std::{fs, path::Path};
+use thiserror::Error;
+
+#[derive(Asset, TypePath, Deserialize)]
+pub struct ShipDefinition {
+    #[serde(default)]
+    pub mesh: String,
+    #[serde(default)]
+    pub material: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub mass: f32,
+    #[serde(default)]
+    pub collider: MeshColliderShape,
+    #[serde(default)]
+    pub fuel_tanks: FuelTanks,
+    #[serde(default)]
+    pub thrusters: Vec<Thruster>,
+    #[serde(default)]
+    pub thruster_mounts: Option<String>,
+    #[serde(default)]
+    pub weapons: Vec<Hardpoint>,
+    #[serde(default)]
+    pub thruster_classifier: ThrusterClassifier,
+    #[serde(default)]
+    pub scheduled_failures: ScheduledFailures,
+    #[serde(default)]
+    pub orientation_regulator: OrientationRegulator,
+    #[serde(default)]
+    pub velocity_regulator: VelocityRegulator,
+    #[serde(default)]
+    pub position_regulator: PositionRegulator,
+    #[serde(skip)]
+    #[dependency]
+    pub mesh_handle: Option<Handle<Mesh>>,
+    #[serde(skip)]
+    #[dependency]
+    pub material_handle: Option<Handle<StandardMaterial>>,
+    #[serde(skip)]
+    #[dependency]
+    pub model_handle: Option<Handle<Gltf>>,
+    #[serde(skip)]
+    #[dependency]
+    pub thruster_mounts_handle: Option<Handle<Gltf>>,
+}
+
+#[derive(Default)]
+pub struct ShipDefinitionLoader;
+
+#[derive(Debug, Error)]
+pub enum ShipDefinitionLoaderError {
+    #[error("Could not read ship definition: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse ship definition: {0}")]
+    Ron(#[from] SpannedError),
+}
+
+impl AssetLoader for ShipDefinitionLoader {
+    type Asset = ShipDefinition;
+    type Settings = ();
+    type Error = ShipDefinitionLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let mut definition = ShipDefinition::from_bytes(&bytes)?;
+        if let Some(model) = &definition.model {
+            definition.model_handle = Some(load_context.load(model));
+        } else {
+            definition.mesh_handle = Some(load_context.load(&definition.mesh));
+            definition.material_handle = Some(load_context.load(&definition.material));
+        }
+        definition.thruster_mounts_handle = definition
+            .thruster_mounts
+            .as_ref()
+            .map(|path| load_context.load(path));
+
+        Ok(definition)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ship.ron"]
+    }
+}
+
+pub fn ship_body() -> impl Bundle {
+    (
+        RigidBody::Dynamic,
+        GravityScale(0.0),
+        ReadMassProperties::default(),
+        ExternalForce::default(),
+        ExternalImpulse::default(),
+        Velocity::default(),
+        Sleeping::disabled(),
+        ControlAuthority::default(),
+    )
+}
+
+pub fn spawn_ship<'a>(
+    commands: &'a mut Commands,
+    definition: Handle<ShipDefinition>,
+    transform: Transform,
+) -> EntityCommands<'a> {
+    commands.spawn((
+        definition,
+        SpatialBundle::from_transform(transform),
+        ship_body(),
+    ))
+}
+
+pub fn spawn_headless_ship<'a>(
+    commands: &'a mut Commands,
+    definition: &ShipDefinition,
+    transform: Transform,
+) -> EntityCommands<'a> {
+    let mut ship = commands.spawn((
+        TransformBundle::from_transform(transform),
+        ship_body(),
+        Collider::cuboid(1.0, 1.0, 4.0),
+    ));
+    definition.insert_flight_model(&mut ship, definition.thrusters.clone());
+    ship
+}
+
+impl ShipDefinition {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ShipDefinitionLoaderError> {
+        Ok(ron::de::from_bytes(bytes)?)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ShipDefinitionLoaderError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn insert_flight_model(&self, ship: &mut EntityCommands, thrusters: Vec<Thruster>) {
+        let mass = self.mass;
+        let thruster_classifier = self.thruster_classifier.clone();
+        let thrusters = Thrusters::new(thrusters);
+        let orientation_regulator = self.orientation_regulator.clone();
+        let velocity_regulator = self.velocity_regulator.clone();
+        let position_regulator = self.position_regulator.clone();
+        let fuel_tanks = (!self.fuel_tanks.tanks.is_empty()).then(|| self.fuel_tanks.clone());
+        let weapons = (!self.weapons.is_empty()).then(|| Weapons {
+            hardpoints: self.weapons.clone(),
+            ..Default::default()
+        });
+        let scheduled_failures =
+            (!self.scheduled_failures.failures.is_empty()).then(|| self.scheduled_failures.clone());
+
+        ship.add(move |mut entity: EntityWorldMut| {
+            entity.insert((ColliderMassProperties::Mass(mass), thruster_classifier));
+            reconfigure(&mut entity, thrusters, Thrusters::reconfigure);
+            reconfigure(
+                &mut entity,
+                orientation_regulator,
+                OrientationRegulator::reconfigure,
+            );
+            reconfigure(
+                &mut entity,
+                velocity_regulator,
+                VelocityRegulator::reconfigure,
+            );
+            reconfigure(
+                &mut entity,
+                position_regulator,
+                PositionRegulator::reconfigure,
+            );
+
+            match fuel_tanks {
+                Some(fuel_tanks) => reconfigure(&mut entity, fuel_tanks, FuelTanks::reconfigure),
+                None => {
+                    entity.remove::<(FuelTanks, AdditionalMassProperties)>();
+                }
+            }
+
+            match weapons {
+                Some(weapons) => reconfigure(&mut entity, weapons, Weapons::reconfigure),
+                None => {
+                    entity.remove::<Weapons>();
+                }
+            }
+
+            match scheduled_failures {
+                Some(failures) => {
+                    reconfigure(&mut entity, failures, ScheduledFailures::reconfigure)
+                }
+                None => {
+                    entity.remove::<ScheduledFailures>();
+                }
+            }
+        });
+    }
+}
+
+fn reconfigure<T: Component>(
+    entity: &mut EntityWorldMut,
+    definition: T,
+    reconfigure: impl FnOnce(&mut T, T),
+) {
+    if let Some(mut component) = entity.get_mut::<T>() {
+        reconfigure(&mut component, definition);
+    } else {
+        entity.insert(definition);
+    }
+}
+
+pub fn apply_ship_definitions(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<ShipDefinition>>,
+    mut gltf_events: EventReader<AssetEvent<Gltf>>,
+    definitions: Res<Assets<ShipDefinition>>,
+    gltfs: Res<Assets<Gltf>>,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    ships: Query<(Entity, Ref<Handle<ShipDefinition>>)>,
+) {
+    let mut changed: Vec<AssetId<ShipDefinition>> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for event in gltf_events.read() {
+        if let AssetEvent::Modified { id } = event {
+            changed.extend(
+                definitions
+                    .iter()
+                    .filter_map(|(definition_id, definition)| {
+                        let handle = definition.thruster_mounts_handle.as_ref()?;
+                        (handle.id() == *id).then_some(definition_id)
+                    }),
+            );
+        }
+    }
+
+    for (entity, handle) in ships.iter() {
+        if !handle.is_added() && !changed.contains(&handle.id()) {
+            continue;
+        }
+
+        let Some(definition) = definitions.get(&*handle) else {
+            continue;
+        };
+
+        let mut thrusters = definition.thrusters.clone();
+        if let Some(mounts) = &definition.thruster_mounts_handle {
+            let Some(gltf) = gltfs.get(mounts) else {
+                continue;
+            };
+            thrusters.extend(thrusters_from_gltf(
+                gltf,
+                &gltf_nodes,
+                definition.model.is_some(),
+            ));
+        }
+
+        let mut ship = commands.entity(entity);
+        if let Some(model) = &definition.model_handle {
+            let Some(scene) = gltfs
+                .get(model)
+                .and_then(|gltf| gltf.default_scene.as_ref().or(gltf.scenes.first()))
+            else {
+                warn!("Model {:?} has no scene", definition.model);
+                continue;
+            };
+
+            ship.insert((
+                scene.clone(),
+                GltfColliderLoader::new(model.clone(), definition.collider.clone()),
+            ));
+        } else if let (Some(mesh), Some(material)) =
+            (&definition.mesh_handle, &definition.material_handle)
+        {
+            ship.insert((
+                mesh.clone(),
+                material.clone(),
+                DeferColliderLoader::new(definition.collider.clone()),
+            ));
+        }
+        definition.insert_flight_model(&mut ship, thrusters);
+    }
+}
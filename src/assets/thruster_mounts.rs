@@ -0,0 +1,170 @@
+use crate::components::thrusters::{Thruster, ThrusterGroup};
+use bevy::{
+    gltf::{Gltf, GltfNode},
+    prelude::*,
+    utils::HashSet,
+};
+use serde::Deserialize;
+
+#[derive(Default, Deserialize)]
+struct ThrusterExtras {
+    group: Option<String>,
+    thrust: Option<f32>,
+    specific_impulse: Option<f32>,
+    spool_up_time: Option<f32>,
+    spool_down_time: Option<f32>,
+    min_impulse_bit: Option<f32>,
+}
+
+fn strip_duplicate_suffix(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((base, suffix))
+            if suffix.len() == 3
+                && suffix.bytes().all(|b| b.is_ascii_digit())
+                && base.matches('.').count() >= 2 =>
+        {
+            base
+        }
+        _ => name,
+    }
+}
+
+fn thruster_from_node(node: &GltfNode, transform: Transform) -> Option<Thruster> {
+    let mut parts = strip_duplicate_suffix(&node.name).splitn(3, '.');
+    if parts.next() != Some("thruster") {
+        return None;
+    }
+
+    let extras: ThrusterExtras = match &node.extras {
+        Some(extras) => match serde_json::from_str(&extras.value) {
+            Ok(extras) => extras,
+            Err(err) => {
+                warn!("Ignoring extras of thruster node {}: {err}", node.name);
+                ThrusterExtras::default()
+            }
+        },
+        None => ThrusterExtras::default(),
+    };
+
+    let group = extras.group.as_deref().or(parts.next());
+    let group = match group.map(str::parse::<ThrusterGroup>) {
+        Some(Ok(group)) => group,
+        Some(Err(err)) => {
+            warn!("Ignoring thruster node {}: {err}", node.name);
+            return None;
+        }
+        None => {
+            warn!("Ignoring thruster node {}: No thruster group", node.name);
+            return None;
+        }
+    };
+
+    let thrust = match (extras.thrust, parts.next()) {
+        (Some(thrust), _) => thrust,
+        (None, Some(thrust)) => match thrust.parse() {
+            Ok(thrust) => thrust,
+            Err(_) => {
+                warn!(
+                    "Ignoring thruster node {}: Invalid thrust {thrust}",
+                    node.name
+                );
+                return None;
+            }
+        },
+        (None, None) => {
+            warn!("Ignoring thruster node {}: No thrust", node.name);
+            return None;
+        }
+    };
+
+    Some(Thruster {
+        offset: transform.translation,
+        direction: transform.rotation,
+        thrust,
+        group,
+        specific_impulse: extras.specific_impulse.unwrap_or_default(),
+        spool_up_time: extras.spool_up_time.unwrap_or_default(),
+        spool_down_time: extras.spool_down_time.unwrap_or_default(),
+        min_impulse_bit: extras.min_impulse_bit.unwrap_or_default(),
+        ..Default::default()
+    })
+}
+
+pub fn global_node_transforms<'a>(
+    gltf: &Gltf,
+    nodes: &'a Assets<GltfNode>,
+) -> Vec<(&'a GltfNode, GlobalTransform)> {
+    let nodes: Vec<&GltfNode> = gltf
+        .nodes
+        .iter()
+        .filter_map(|handle| nodes.get(handle))
+        .collect();
+
+    let children: HashSet<usize> = nodes
+        .iter()
+        .flat_map(|node| node.children.iter().map(|child| child.index))
+        .collect();
+
+    let mut global_transforms = Vec::new();
+    let mut pending: Vec<(&GltfNode, GlobalTransform)> = nodes
+        .iter()
+        .filter(|node| !children.contains(&node.index))
+        .map(|node| (*node, GlobalTransform::from(node.transform)))
+        .collect();
+
+    while let Some((node, global_transform)) = pending.pop() {
+        for child in &node.children {
+            pending.push((child, global_transform.mul_transform(child.transform)));
+        }
+        global_transforms.push((node, global_transform));
+    }
+
+    global_transforms.sort_by_key(|(node, _)| node.index);
+    global_transforms
+}
+
+pub fn thrusters_from_gltf(
+    gltf: &Gltf,
+    nodes: &Assets<GltfNode>,
+    scene_space: bool,
+) -> Vec<Thruster> {
+    let global_transforms = global_node_transforms(gltf, nodes);
+
+    let origin = global_transforms
+        .iter()
+        .find(|(node, _)| node.mesh.is_some() && !scene_space)
+        .map(|(_, global_transform)| *global_transform)
+        .unwrap_or_default();
+
+    global_transforms
+        .iter()
+        .filter_map(|(node, global_transform)| {
+            thruster_from_node(node, global_transform.reparented_to(&origin))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_duplicate_suffix;
+
+    #[test]
+    fn duplicate_suffix_is_stripped_after_thrust() {
+        assert_eq!(
+            strip_duplicate_suffix("thruster.FORWARD.12.5"),
+            "thruster.FORWARD.12.5"
+        );
+        assert_eq!(
+            strip_duplicate_suffix("thruster.FORWARD.12.5.001"),
+            "thruster.FORWARD.12.5"
+        );
+        assert_eq!(
+            strip_duplicate_suffix("thruster.FORWARD.12.001"),
+            "thruster.FORWARD.12"
+        );
+        assert_eq!(
+            strip_duplicate_suffix("thruster.FORWARD.001"),
+            "thruster.FORWARD.001"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Usage: `headless [--ticks N] [--ship FILE] [--output FILE] [--telemetry FILE]`
+//!
+//! The telemetry of every tick is written as a JSON array if the file ends in `.json`, as JSON
+//! Lines for `.jsonl` and as CSV otherwise.
+//!
+//! `headless --benchmark [--output FILE]` runs the stress test benchmark instead and writes the
+//! average physics step time of every configuration.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Serialize;
+use space_battle::{
+    assets::ship_definition::{spawn_headless_ship, ShipDefinition},
+    components::{
+        flight_recorder::FlightRecorder,
+        fuel_tanks::FuelTanks,
+        target::{Target, Targeting},
+        weapons::Gunner,
+    },
+    headless_app,
+    stress_test::StressTestBenchmark,
+    SimulationTick, SIMULATION_HZ,
+};
+use std::{env, error::Error, fs};
+
+const DEFAULT_TICKS: u64 = 600;
+const DEFAULT_SHIP: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/ships/player.ship.ron");
+const TARGET: Vec3 = Vec3::new(20.0, 10.0, -30.0);
+
+#[derive(Serialize)]
+struct ScenarioResult {
+    ticks: u64,
+    seconds: f32,
+    position: Vec3,
+    rotation: Quat,
+    linear_velocity: Vec3,
+    angular_velocity: Vec3,
+    pointing_error: f32,
+    fuel: Option<f32>,
+}
+
+struct Args {
+    ticks: u64,
+    ship: String,
+    output: Option<String>,
+    telemetry: Option<String>,
+    benchmark: bool,
+}
+
+fn parse_args() -> Result<Args, Box<dyn Error>> {
+    let mut args = Args {
+        ticks: DEFAULT_TICKS,
+        ship: DEFAULT_SHIP.to_string(),
+        output: None,
+        telemetry: None,
+        benchmark: false,
+    };
+
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("Missing value for {arg}"));
+        match arg.as_str() {
+            "--ticks" => args.ticks = value()?.parse()?,
+            "--ship" => args.ship = value()?,
+            "--output" => args.output = Some(value()?),
+            "--telemetry" => args.telemetry = Some(value()?),
+            "--benchmark" => args.benchmark = true,
+            _ => return Err(format!("Unknown argument {arg}").into()),
+        }
+    }
+
+    Ok(args)
+}
+
+fn write_output(output: &Option<String>, json: String) -> Result<(), Box<dyn Error>> {
+    match output {
+        Some(path) => fs::write(path, json)?,
+        None => println!("{json}"),
+    }
+
+    Ok(())
+}
+
+fn run_benchmark(args: &Args) -> Result<(), Box<dyn Error>> {
+    let mut app = headless_app();
+    app.insert_resource(StressTestBenchmark::default());
+
+    while !app.world().resource::<StressTestBenchmark>().finished() {
+        app.update();
+    }
+
+    let results = &app.world().resource::<StressTestBenchmark>().results;
+    write_output(&args.output, serde_json::to_string_pretty(results)?)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = parse_args()?;
+    if args.benchmark {
+        return run_benchmark(&args);
+    }
+
+    let definition = ShipDefinition::from_file(&args.ship)?;
+
+    let recorder = FlightRecorder::with_capacity(args.ticks as usize);
+
+    let mut app = headless_app();
+    app.add_systems(Startup, move |mut commands: Commands| {
+        let target = commands
+            .spawn((
+                Name::new("Target"),
+                TransformBundle::from_transform(Transform::from_translation(TARGET)),
+                Target,
+            ))
+            .id();
+
+        spawn_headless_ship(&mut commands, &definition, Transform::default()).insert((
+            Name::new("Ship"),
+            recorder.clone(),
+            Targeting::locked(target),
+            Gunner::default(),
+        ));
+    });
+
+    while app.world().resource::<SimulationTick>().0 < args.ticks {
+        app.update();
+    }
+
+    let world = app.world_mut();
+    let ticks = world.resource::<SimulationTick>().0;
+    let (transform, velocity, fuel_tanks, recorder) = world
+        .query_filtered::<(
+            &Transform,
+            &Velocity,
+            Option<&FuelTanks>,
+            &FlightRecorder,
+        ), Without<Target>>()
+        .single(world);
+
+    if let Some(path) = &args.telemetry {
+        recorder.write_to_file(path)?;
+    }
+
+    let forward = transform.rotation * Vec3::NEG_Z;
+    let result = ScenarioResult {
+        ticks,
+        seconds: (ticks as f64 / SIMULATION_HZ) as f32,
+        position: transform.translation,
+        rotation: transform.rotation,
+        linear_velocity: velocity.linvel,
+        angular_velocity: velocity.angvel,
+        pointing_error: forward
+            .angle_between(TARGET - transform.translation)
+            .to_degrees(),
+        fuel: fuel_tanks.map(FuelTanks::fuel),
+    };
+
+    write_output(&args.output, serde_json::to_string_pretty(&result)?)
+}
@@ -1,6 +1,18 @@
+pub mod control_authority;
 pub mod defer_collider_loader;
-pub mod max_torque;
+pub mod flight_assist;
+pub mod flight_recorder;
+pub mod fuel_tanks;
+pub mod gltf_collider_loader;
+pub mod intercept;
+pub mod mouse_aim;
 pub mod orientation_regulator;
 pub mod player_ship;
+pub mod position_regulator;
 pub mod target;
+pub mod thrust_allocation;
+pub mod thruster_classifier;
+pub mod thruster_failures;
 pub mod thrusters;
+pub mod velocity_regulator;
+pub mod weapons;
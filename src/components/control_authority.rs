@@ -0,0 +1,131 @@
+use super::{
+    thrust_allocation::Wrench,
+    thrusters::{ThrusterGroup, Thrusters},
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::ReadMassProperties;
+use serde::{Deserialize, Serialize};
+
+#[derive(Component, Default, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+pub struct ControlAuthority {
+    #[serde(skip)]
+    pub positive_force: Vec3,
+    #[serde(skip)]
+    pub negative_force: Vec3,
+    #[serde(skip)]
+    pub positive_torque: Vec3,
+    #[serde(skip)]
+    pub negative_torque: Vec3,
+    /// Wrench of every group firing at full thrust, indexed by `ThrusterGroup::index`.
+    #[serde(skip)]
+    pub group_capacity: [Wrench; 12],
+}
+
+impl ControlAuthority {
+    pub fn group_wrench(&self, group: ThrusterGroup, magnitude: f32) -> Wrench {
+        let capacity = self.group_capacity[group.index()];
+        let mut wrench = Wrench::ZERO;
+
+        // Only the axis the group is named after, whatever else its thrusters push on
+        for axis in 0..3 {
+            let translation = ThrusterGroup::positive_translation(axis)
+                | ThrusterGroup::negative_translation(axis);
+            if group.intersects(translation) {
+                wrench.force[axis] = capacity.force[axis];
+            }
+
+            let rotation =
+                ThrusterGroup::positive_rotation(axis) | ThrusterGroup::negative_rotation(axis);
+            if group.intersects(rotation) {
+                wrench.torque[axis] = capacity.torque[axis];
+            }
+        }
+
+        wrench * magnitude
+    }
+}
+
+pub fn update_control_authority(
+    mut query: Query<(
+        Ref<Thrusters>,
+        Ref<ReadMassProperties>,
+        &mut ControlAuthority,
+    )>,
+) {
+    for (thrusters, mass_props, mut authority) in query.iter_mut() {
+        if !thrusters.is_changed() && !mass_props.is_changed() {
+            continue;
+        }
+
+        let center_of_mass = mass_props.get().local_center_of_mass;
+
+        let mut positive_force = Vec3::ZERO;
+        let mut negative_force = Vec3::ZERO;
+        let mut positive_torque = Vec3::ZERO;
+        let mut negative_torque = Vec3::ZERO;
+        let mut group_capacity = [Wrench::ZERO; 12];
+
+        for thruster in &thrusters.thrusters {
+            let fraction = thruster.failure.controllable_fraction();
+            let force = fraction * thruster.local_force();
+            let torque = fraction * thruster.local_torque(center_of_mass);
+
+            positive_force += force.max(Vec3::ZERO);
+            negative_force += (-force).max(Vec3::ZERO);
+            positive_torque += torque.max(Vec3::ZERO);
+            negative_torque += (-torque).max(Vec3::ZERO);
+
+            for (_, group) in ThrusterGroup::NAMES {
+                if thruster.group.intersects(group) {
+                    group_capacity[group.index()] += Wrench::new(force, torque);
+                }
+            }
+        }
+
+        *authority = ControlAuthority {
+            positive_force,
+            negative_force,
+            positive_torque,
+            negative_torque,
+            group_capacity,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{update_control_authority, ControlAuthority};
+    use crate::components::{
+        thruster_failures::ThrusterFailure,
+        thrusters::{Thruster, Thrusters},
+    };
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::ReadMassProperties;
+
+    #[test]
+    fn edited_thrusters_are_picked_up() {
+        let mut world = World::new();
+        let ship = world
+            .spawn((
+                ReadMassProperties::default(),
+                ControlAuthority::default(),
+                Thrusters::new(vec![Thruster {
+                    thrust: 10.0,
+                    ..Default::default()
+                }]),
+            ))
+            .id();
+
+        let system = world.register_system(update_control_authority);
+
+        world.run_system(system).unwrap();
+        let authority = world.get::<ControlAuthority>(ship).unwrap();
+        assert_eq!(authority.positive_force, Vec3::new(0.0, 0.0, 10.0));
+
+        world.get_mut::<Thrusters>(ship).unwrap().thrusters[0].failure = ThrusterFailure::StuckOff;
+        world.run_system(system).unwrap();
+        let authority = world.get::<ControlAuthority>(ship).unwrap();
+        assert_eq!(authority.positive_force, Vec3::ZERO);
+    }
+}
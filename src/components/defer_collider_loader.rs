@@ -1,27 +1,171 @@
-use bevy::{asset::LoadState, prelude::*};
+use crate::{
+    assets::collider_cache::{ColliderCache, ColliderCacheError},
+    entity_label,
+};
+use bevy::{asset::LoadState, prelude::*, render::primitives::Aabb};
 use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Clone, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConvexDecomposition {
+    pub resolution: u32,
+    pub concavity: f32,
+    pub max_convex_hulls: u32,
+    pub cache: bool,
+}
+
+impl Default for ConvexDecomposition {
+    fn default() -> Self {
+        Self {
+            resolution: 64,
+            concavity: 0.01,
+            max_convex_hulls: 1024,
+            cache: true,
+        }
+    }
+}
+
+impl ConvexDecomposition {
+    pub fn parameters(&self) -> VHACDParameters {
+        VHACDParameters {
+            resolution: self.resolution,
+            concavity: self.concavity,
+            max_convex_hulls: self.max_convex_hulls,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub enum MeshColliderShape {
+    TriMesh,
+    #[default]
+    ConvexHull,
+    ConvexDecomposition(ConvexDecomposition),
+}
+
+impl MeshColliderShape {
+    pub fn computed(&self) -> ComputedColliderShape {
+        match self {
+            MeshColliderShape::TriMesh => ComputedColliderShape::TriMesh,
+            MeshColliderShape::ConvexHull => ComputedColliderShape::ConvexHull,
+            MeshColliderShape::ConvexDecomposition(decomposition) => {
+                ComputedColliderShape::ConvexDecomposition(decomposition.parameters())
+            }
+        }
+    }
+}
 
 #[derive(Debug, Default, Reflect, Component)]
-pub struct DeferColliderLoader;
+#[reflect(Component)]
+pub struct DeferColliderLoader {
+    pub shape: MeshColliderShape,
+}
+
+impl DeferColliderLoader {
+    pub fn new(shape: MeshColliderShape) -> Self {
+        Self { shape }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ColliderLoadError {
+    #[error("Mesh failed to load: {0}")]
+    MeshFailed(String),
+    #[error("Could not compute a {0:?} collider, using the bounding box instead")]
+    ShapeFailed(MeshColliderShape),
+    #[error("Mesh has no vertices to compute a collider from")]
+    NoVertices,
+    #[error("Model has no meshes to compute a collider from")]
+    NoMeshes,
+    #[error("A TriMesh cannot be combined with other parts, using its convex hull instead")]
+    MixedTriMesh,
+}
+
+#[derive(Event, Debug)]
+pub struct ColliderLoadFailed {
+    pub entity: Entity,
+    pub error: ColliderLoadError,
+}
+
+pub fn bounding_box(aabb: &Aabb) -> (Vec3, Collider) {
+    let half_extents = Vec3::from(aabb.half_extents);
+    (
+        Vec3::from(aabb.center),
+        Collider::cuboid(half_extents.x, half_extents.y, half_extents.z),
+    )
+}
+
+pub fn compute_collider(
+    cache: &ColliderCache,
+    shape: &MeshColliderShape,
+    handle: &Handle<Mesh>,
+    mesh: &Mesh,
+) -> Option<Collider> {
+    if let (MeshColliderShape::ConvexDecomposition(decomposition), Some(path)) =
+        (shape, handle.path())
+    {
+        if decomposition.cache {
+            match cache.convex_decomposition(path, mesh, decomposition) {
+                Ok(collider) => return Some(collider),
+                // Decomposing again without the cache would give the same degenerate hulls
+                Err(ColliderCacheError::InvalidParts) => return None,
+                Err(err) => warn!("Could not use collider cache for {path}: {err}"),
+            }
+        }
+    }
+
+    Collider::from_bevy_mesh(mesh, &shape.computed())
+}
 
 pub fn defer_collider_loader(
     mut commands: Commands,
+    mut failures: EventWriter<ColliderLoadFailed>,
     meshes: Res<Assets<Mesh>>,
     server: Res<AssetServer>,
-    query: Query<(Entity, &DeferColliderLoader, &Handle<Mesh>)>,
+    cache: Res<ColliderCache>,
+    query: Query<(Entity, Option<&Name>, &DeferColliderLoader, &Handle<Mesh>)>,
 ) {
-    for (e, _, m) in query.iter() {
-        if let Some(LoadState::Loaded) = server.get_load_state(m) {
-            let collider = Collider::from_bevy_mesh(
-                meshes.get(m).unwrap(),
-                &ComputedColliderShape::ConvexHull,
-            )
-            .unwrap();
-
-            commands
-                .entity(e)
-                .remove::<DeferColliderLoader>()
-                .insert(collider);
+    for (entity, name, loader, handle) in query.iter() {
+        let result = match meshes.get(handle) {
+            Some(mesh) => match compute_collider(&cache, &loader.shape, handle, mesh) {
+                Some(collider) => Ok(collider),
+                None => match mesh.compute_aabb() {
+                    Some(aabb) => {
+                        let collider = match bounding_box(&aabb) {
+                            (Vec3::ZERO, cuboid) => cuboid,
+                            (center, cuboid) => {
+                                Collider::compound(vec![(center, Quat::IDENTITY, cuboid)])
+                            }
+                        };
+                        commands.entity(entity).insert(collider);
+                        Err(ColliderLoadError::ShapeFailed(loader.shape.clone()))
+                    }
+                    None => Err(ColliderLoadError::NoVertices),
+                },
+            },
+            None => match server.get_load_state(handle) {
+                Some(LoadState::Failed(err)) => Err(ColliderLoadError::MeshFailed(err.to_string())),
+                _ => continue,
+            },
+        };
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<DeferColliderLoader>();
+
+        match result {
+            Ok(collider) => {
+                entity_commands.insert(collider);
+            }
+            Err(error) => {
+                let label = entity_label(entity, name);
+                warn!("Collider for {label}: {error}");
+                failures.send(ColliderLoadFailed { entity, error });
+            }
         }
     }
 }
@@ -0,0 +1,352 @@
+use super::{
+    control_authority::ControlAuthority,
+    mouse_aim::MouseAim,
+    orientation_regulator::OrientationRegulator,
+    position_regulator::PositionRegulator,
+    target::Targeting,
+    thrusters::{ThrusterGroup, Thrusters},
+    velocity_regulator::VelocityRegulator,
+};
+use crate::{
+    entity_label,
+    input::{ActionPresses, InputAction, InputMap, InputState},
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub enum FlightAssistMode {
+    Off,
+    RotationDamping,
+    Decoupled,
+    #[default]
+    Coupled,
+}
+
+impl FlightAssistMode {
+    pub const ALL: [FlightAssistMode; 4] = [
+        FlightAssistMode::Off,
+        FlightAssistMode::RotationDamping,
+        FlightAssistMode::Decoupled,
+        FlightAssistMode::Coupled,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn damps_rotation(self) -> bool {
+        self != FlightAssistMode::Off
+    }
+}
+
+#[derive(Component, Clone, Default, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlightAssist {
+    pub mode: FlightAssistMode,
+    #[serde(skip)]
+    held_rotation: Option<Quat>,
+    #[serde(skip)]
+    held_velocity: Option<Vec3>,
+    #[serde(skip)]
+    held_speed: Option<f32>,
+}
+
+impl FlightAssist {
+    pub fn new(mode: FlightAssistMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+}
+
+fn stopping_rotation(
+    rotation: Quat,
+    local_angvel: Vec3,
+    authority: &ControlAuthority,
+    mass_props: &MassProperties,
+) -> Quat {
+    // The inertia is given around the principal axes, which need not be the ship's axes
+    let frame = mass_props.principal_inertia_local_frame;
+    let angvel = frame.inverse() * local_angvel;
+    let symmetric_torque = authority.positive_torque.min(authority.negative_torque);
+
+    let mut stopping_angle = Vec3::ZERO;
+    for axis in 0..3 {
+        let direction = frame * Vec3::AXES[axis];
+        let torque = (0..3)
+            .filter(|&i| direction[i].abs() > f32::EPSILON)
+            .map(|i| symmetric_torque[i] / direction[i].abs())
+            .fold(f32::INFINITY, f32::min);
+        let angular_inertia = mass_props.principal_inertia[axis];
+
+        if torque.is_finite() && torque > 0.0 && angular_inertia > 0.0 {
+            let angular_acceleration = torque / angular_inertia;
+            stopping_angle[axis] = angvel[axis] * angvel[axis].abs() / (2.0 * angular_acceleration);
+        }
+    }
+
+    rotation * Quat::from_scaled_axis(frame * stopping_angle)
+}
+
+#[allow(clippy::type_complexity)]
+pub fn flight_assist(
+    mut query: Query<(
+        &Transform,
+        &Velocity,
+        &ReadMassProperties,
+        &ControlAuthority,
+        &Thrusters,
+        Option<&MouseAim>,
+        Option<&Targeting>,
+        Option<&PositionRegulator>,
+        &mut FlightAssist,
+        &mut OrientationRegulator,
+        &mut VelocityRegulator,
+    )>,
+) {
+    let rotation_groups = (0..3).fold(ThrusterGroup::NONE, |groups, axis| {
+        groups | ThrusterGroup::positive_rotation(axis) | ThrusterGroup::negative_rotation(axis)
+    });
+    let translation_groups = (0..3).fold(ThrusterGroup::NONE, |groups, axis| {
+        groups
+            | ThrusterGroup::positive_translation(axis)
+            | ThrusterGroup::negative_translation(axis)
+    });
+
+    for (
+        transform,
+        velocity,
+        mass_props,
+        authority,
+        thrusters,
+        aim,
+        targeting,
+        position_regulator,
+        mut assist,
+        mut orientation_regulator,
+        mut velocity_regulator,
+    ) in query.iter_mut()
+    {
+        let assist = assist.as_mut();
+
+        let rotating = thrusters.groups_to_fire.intersects(rotation_groups)
+            || thrusters.wrench.torque != Vec3::ZERO;
+        let translating = thrusters.groups_to_fire.intersects(translation_groups)
+            || thrusters.wrench.force != Vec3::ZERO;
+
+        if !aim.is_some_and(|aim| aim.enable) && !targeting.is_some_and(Targeting::following) {
+            if rotating || !assist.mode.damps_rotation() {
+                assist.held_rotation = None;
+                orientation_regulator.set_enabled(false);
+            } else {
+                let held_rotation = *assist.held_rotation.get_or_insert_with(|| {
+                    let local_angvel = transform.rotation.inverse() * velocity.angvel;
+                    stopping_rotation(
+                        transform.rotation,
+                        local_angvel,
+                        authority,
+                        mass_props.get(),
+                    )
+                });
+                orientation_regulator.update_target(held_rotation);
+                orientation_regulator.set_enabled(true);
+            }
+        }
+
+        // Flying to a point takes precedence over holding a velocity
+        if position_regulator.is_some_and(PositionRegulator::enabled) {
+            continue;
+        }
+
+        if translating {
+            assist.held_velocity = None;
+            assist.held_speed = None;
+            velocity_regulator.set_enabled(false);
+            continue;
+        }
+
+        match assist.mode {
+            FlightAssistMode::Off | FlightAssistMode::RotationDamping => {
+                assist.held_velocity = None;
+                assist.held_speed = None;
+                velocity_regulator.set_enabled(false);
+            }
+            FlightAssistMode::Decoupled => {
+                let held_velocity = *assist.held_velocity.get_or_insert(velocity.linvel);
+                velocity_regulator.update_target(held_velocity);
+                velocity_regulator.set_enabled(true);
+            }
+            FlightAssistMode::Coupled => {
+                let forward = *transform.forward();
+                let held_speed = *assist
+                    .held_speed
+                    .get_or_insert(velocity.linvel.dot(forward));
+                velocity_regulator.update_target(held_speed * forward);
+                velocity_regulator.set_enabled(true);
+            }
+        }
+    }
+}
+
+pub fn cycle_flight_assist(
+    input_map: Res<InputMap>,
+    input: InputState,
+    mut presses: Local<ActionPresses>,
+    mut query: Query<(Entity, Option<&Name>, &mut FlightAssist)>,
+) {
+    if !presses.just_pressed(&input_map, &input, InputAction::CycleFlightAssist) {
+        return;
+    }
+
+    for (entity, name, mut assist) in query.iter_mut() {
+        assist.mode = assist.mode.next();
+
+        let ship = entity_label(entity, name);
+        info!("Flight assist of {ship}: {:?}", assist.mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flight_assist, stopping_rotation, FlightAssist, FlightAssistMode};
+    use crate::components::{
+        control_authority::ControlAuthority, orientation_regulator::OrientationRegulator,
+        thrusters::Thrusters, velocity_regulator::VelocityRegulator,
+    };
+    use bevy::{ecs::system::RunSystemOnce, prelude::*};
+    use bevy_rapier3d::prelude::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn spawn_ship(world: &mut World, mode: FlightAssistMode, velocity: Velocity) -> Entity {
+        world
+            .spawn((
+                Transform::from_rotation(Quat::from_rotation_y(0.5)),
+                velocity,
+                ReadMassProperties::default(),
+                ControlAuthority::default(),
+                Thrusters::default(),
+                FlightAssist::new(mode),
+                OrientationRegulator::default(),
+                VelocityRegulator::default(),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn modes_cycle_in_order() {
+        let mut mode = FlightAssistMode::Off;
+        for expected in [
+            FlightAssistMode::RotationDamping,
+            FlightAssistMode::Decoupled,
+            FlightAssistMode::Coupled,
+            FlightAssistMode::Off,
+        ] {
+            mode = mode.next();
+            assert_eq!(mode, expected);
+        }
+    }
+
+    #[test]
+    fn stopping_angle_uses_principal_axes() {
+        let authority = ControlAuthority {
+            positive_torque: Vec3::splat(2.0),
+            negative_torque: Vec3::splat(2.0),
+            ..Default::default()
+        };
+
+        // The principal X axis is the ship's Y axis, with the smallest inertia
+        let mass_props = MassProperties {
+            principal_inertia_local_frame: Quat::from_rotation_z(FRAC_PI_2),
+            principal_inertia: Vec3::new(1.0, 4.0, 4.0),
+            ..Default::default()
+        };
+
+        let stop = stopping_rotation(
+            Quat::IDENTITY,
+            Vec3::new(0.0, 2.0, 0.0),
+            &authority,
+            &mass_props,
+        );
+
+        let (axis, angle) = stop.to_axis_angle();
+        assert!((angle - 1.0).abs() < 1e-4, "{angle}");
+        assert!((axis - Vec3::Y).length() < 1e-4, "{axis}");
+    }
+
+    #[test]
+    fn rotation_is_held_until_rotation_is_commanded() {
+        let mut world = World::new();
+        let ship = spawn_ship(
+            &mut world,
+            FlightAssistMode::RotationDamping,
+            Velocity::zero(),
+        );
+
+        world.run_system_once(flight_assist);
+        let regulator = world.get::<OrientationRegulator>(ship).unwrap();
+        assert!(regulator.enabled());
+        assert_eq!(regulator.target(), Quat::from_rotation_y(0.5));
+        assert!(!world.get::<VelocityRegulator>(ship).unwrap().enabled());
+
+        world.get_mut::<Thrusters>(ship).unwrap().wrench.torque = Vec3::Y;
+        world.run_system_once(flight_assist);
+        assert!(!world.get::<OrientationRegulator>(ship).unwrap().enabled());
+    }
+
+    #[test]
+    fn off_leaves_the_ship_alone() {
+        let mut world = World::new();
+        let ship = spawn_ship(&mut world, FlightAssistMode::Off, Velocity::linear(Vec3::X));
+
+        world.run_system_once(flight_assist);
+        assert!(!world.get::<OrientationRegulator>(ship).unwrap().enabled());
+        assert!(!world.get::<VelocityRegulator>(ship).unwrap().enabled());
+    }
+
+    #[test]
+    fn decoupled_holds_velocity() {
+        let mut world = World::new();
+        let velocity = Vec3::new(1.0, 2.0, 3.0);
+        let ship = spawn_ship(
+            &mut world,
+            FlightAssistMode::Decoupled,
+            Velocity::linear(velocity),
+        );
+
+        world.run_system_once(flight_assist);
+        world.get_mut::<Velocity>(ship).unwrap().linvel = Vec3::ZERO;
+        world.run_system_once(flight_assist);
+
+        let regulator = world.get::<VelocityRegulator>(ship).unwrap();
+        assert!(regulator.enabled());
+        assert_eq!(regulator.target(), velocity);
+
+        world.get_mut::<Thrusters>(ship).unwrap().wrench.force = Vec3::X;
+        world.run_system_once(flight_assist);
+        assert!(!world.get::<VelocityRegulator>(ship).unwrap().enabled());
+    }
+
+    #[test]
+    fn coupled_holds_speed_along_the_nose() {
+        let mut world = World::new();
+        let ship = spawn_ship(
+            &mut world,
+            FlightAssistMode::Coupled,
+            Velocity::linear(Vec3::new(3.0, 1.0, -5.0)),
+        );
+
+        world.run_system_once(flight_assist);
+
+        let forward = Quat::from_rotation_y(0.5) * Vec3::NEG_Z;
+        let speed = Vec3::new(3.0, 1.0, -5.0).dot(forward);
+        let regulator = world.get::<VelocityRegulator>(ship).unwrap();
+        assert!(regulator.enabled());
+        assert!((regulator.target() - speed * forward).length() < 1e-5);
+    }
+}
@@ -0,0 +1,277 @@
+use super::{
+    orientation_regulator::OrientationRegulator,
+    thrusters::{ThrusterGroup, Thrusters},
+};
+use crate::{
+    entity_label,
+    input::{ActionPresses, InputAction, InputMap, InputState},
+    SimulationTick,
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Serialize)]
+pub struct TelemetrySample {
+    pub tick: u64,
+    pub time: f32,
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub linvel: Vec3,
+    pub angvel: Vec3,
+    pub target_rotation: Option<Quat>,
+    pub target_angvel: Option<Vec3>,
+    pub local_angvel: Option<Vec3>,
+    pub groups_to_fire: u32,
+    pub group_thrust: [f32; 12],
+    pub thruster_output: Vec<f32>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub enum TelemetryFormat {
+    #[default]
+    Csv,
+    Json,
+    JsonLines,
+}
+
+impl TelemetryFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TelemetryFormat::Csv => "csv",
+            TelemetryFormat::Json => "json",
+            TelemetryFormat::JsonLines => "jsonl",
+        }
+    }
+
+    /// JSON for `.json`, JSON Lines for `.jsonl` and CSV for anything else.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => TelemetryFormat::Json,
+            Some("jsonl") => TelemetryFormat::JsonLines,
+            _ => TelemetryFormat::Csv,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct TelemetryExport {
+    pub directory: PathBuf,
+    pub format: TelemetryFormat,
+}
+
+impl Default for TelemetryExport {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("telemetry"),
+            format: TelemetryFormat::Csv,
+        }
+    }
+}
+
+#[derive(Component, Clone, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlightRecorder {
+    pub capacity: usize,
+    #[serde(skip)]
+    #[reflect(ignore)]
+    samples: VecDeque<TelemetrySample>,
+}
+
+impl Default for FlightRecorder {
+    fn default() -> Self {
+        Self::with_capacity(60 * 60)
+    }
+}
+
+impl FlightRecorder {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn samples(&self) -> &VecDeque<TelemetrySample> {
+        &self.samples
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn push(&mut self, sample: TelemetrySample) {
+        while self.samples.len() >= self.capacity.max(1) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn write(&self, writer: impl Write, format: TelemetryFormat) -> io::Result<()> {
+        match format {
+            TelemetryFormat::Csv => self.write_csv(writer),
+            TelemetryFormat::Json => self.write_json(writer),
+            TelemetryFormat::JsonLines => self.write_json_lines(writer),
+        }
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        self.write(
+            BufWriter::new(File::create(path)?),
+            TelemetryFormat::from_path(path),
+        )
+    }
+
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        let thruster_count = self
+            .samples
+            .iter()
+            .map(|sample| sample.thruster_output.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut header = vec!["tick".to_string(), "time".to_string()];
+        for (name, components) in [
+            ("position", "xyz"),
+            ("rotation", "xyzw"),
+            ("linvel", "xyz"),
+            ("angvel", "xyz"),
+            ("target_rotation", "xyzw"),
+            ("target_angvel", "xyz"),
+            ("local_angvel", "xyz"),
+        ] {
+            header.extend(components.chars().map(|c| format!("{name}_{c}")));
+        }
+        header.push("groups_to_fire".to_string());
+        header.extend(
+            ThrusterGroup::NAMES
+                .iter()
+                .map(|(name, _)| format!("group_thrust_{name}")),
+        );
+        header.extend((0..thruster_count).map(|i| format!("thruster_output_{i}")));
+        writeln!(writer, "{}", header.join(","))?;
+
+        for sample in &self.samples {
+            let mut row = vec![sample.tick.to_string(), sample.time.to_string()];
+            row.extend(columns(Some(sample.position.to_array())));
+            row.extend(columns(Some(sample.rotation.to_array())));
+            row.extend(columns(Some(sample.linvel.to_array())));
+            row.extend(columns(Some(sample.angvel.to_array())));
+            row.extend(columns(sample.target_rotation.map(|q| q.to_array())));
+            row.extend(columns(sample.target_angvel.map(|v| v.to_array())));
+            row.extend(columns(sample.local_angvel.map(|v| v.to_array())));
+            row.push(sample.groups_to_fire.to_string());
+            row.extend(columns(Some(sample.group_thrust)));
+            row.extend((0..thruster_count).map(|i| {
+                sample
+                    .thruster_output
+                    .get(i)
+                    .map_or(String::new(), f32::to_string)
+            }));
+            writeln!(writer, "{}", row.join(","))?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn write_json(&self, mut writer: impl Write) -> io::Result<()> {
+        serde_json::to_writer(&mut writer, &self.samples)?;
+        writer.flush()
+    }
+
+    pub fn write_json_lines(&self, mut writer: impl Write) -> io::Result<()> {
+        for sample in &self.samples {
+            serde_json::to_writer(&mut writer, sample)?;
+            writeln!(writer)?;
+        }
+
+        writer.flush()
+    }
+}
+
+fn columns<const N: usize>(values: Option<[f32; N]>) -> [String; N] {
+    match values {
+        Some(values) => values.map(|value| value.to_string()),
+        None => std::array::from_fn(|_| String::new()),
+    }
+}
+
+pub fn record_flight_data(
+    time: Res<Time>,
+    tick: Res<SimulationTick>,
+    mut query: Query<(
+        &Transform,
+        &Velocity,
+        &Thrusters,
+        Option<&OrientationRegulator>,
+        &mut FlightRecorder,
+    )>,
+) {
+    for (transform, velocity, thrusters, regulator, mut recorder) in query.iter_mut() {
+        recorder.push(TelemetrySample {
+            tick: tick.0,
+            time: time.elapsed_seconds(),
+            position: transform.translation,
+            rotation: transform.rotation,
+            linvel: velocity.linvel,
+            angvel: velocity.angvel,
+            target_rotation: regulator.map(OrientationRegulator::target),
+            target_angvel: regulator.map(OrientationRegulator::target_angvel),
+            local_angvel: regulator.map(OrientationRegulator::local_angvel),
+            groups_to_fire: thrusters.groups_to_fire.bits(),
+            group_thrust: thrusters.group_thrust,
+            thruster_output: thrusters
+                .thrusters
+                .iter()
+                .map(|thruster| thruster.output)
+                .collect(),
+        });
+    }
+}
+
+pub fn dump_flight_data(
+    input_map: Res<InputMap>,
+    input: InputState,
+    mut presses: Local<ActionPresses>,
+    export: Res<TelemetryExport>,
+    tick: Res<SimulationTick>,
+    query: Query<(Entity, Option<&Name>, &FlightRecorder)>,
+) {
+    if !presses.just_pressed(&input_map, &input, InputAction::DumpFlightRecording) {
+        return;
+    }
+
+    if let Err(err) = fs::create_dir_all(&export.directory) {
+        error!(
+            "Could not create telemetry directory {}: {err}",
+            export.directory.display()
+        );
+        return;
+    }
+
+    for (entity, name, recorder) in query.iter() {
+        let ship = entity_label(entity, name);
+        let path =
+            export
+                .directory
+                .join(format!("{ship}-{}.{}", tick.0, export.format.extension()));
+
+        let result = File::create(&path)
+            .and_then(|file| recorder.write(BufWriter::new(file), export.format));
+
+        match result {
+            Ok(()) => info!("Wrote flight data of {ship} to {}", path.display()),
+            Err(err) => error!("Could not write {}: {err}", path.display()),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use super::thrusters::Thrusters;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const G0: f32 = 9.80665;
+
+const MASS_UPDATE_FRACTION: f32 = 0.005;
+
+#[derive(Clone, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize)]
+pub struct FuelTank {
+    pub offset: Vec3,
+    pub capacity: f32,
+    pub fuel: f32,
+}
+
+#[derive(Component, Clone, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize)]
+pub struct FuelTanks {
+    pub tanks: Vec<FuelTank>,
+    #[serde(skip)]
+    applied_fuel: Option<f32>,
+}
+
+impl FuelTanks {
+    pub fn reconfigure(&mut self, mut definition: Self) {
+        for (tank, current) in definition.tanks.iter_mut().zip(&self.tanks) {
+            tank.fuel = current.fuel.min(tank.capacity);
+        }
+        self.tanks = definition.tanks;
+        self.applied_fuel = None;
+    }
+
+    pub fn fuel(&self) -> f32 {
+        self.tanks.iter().map(|tank| tank.fuel).sum()
+    }
+
+    pub fn capacity(&self) -> f32 {
+        self.tanks.iter().map(|tank| tank.capacity).sum()
+    }
+
+    pub fn mass_properties(&self) -> MassProperties {
+        let mass = self.fuel();
+        if mass <= 0.0 {
+            return MassProperties::default();
+        }
+
+        let center_of_mass = self
+            .tanks
+            .iter()
+            .map(|tank| tank.fuel * tank.offset)
+            .sum::<Vec3>()
+            / mass;
+
+        let principal_inertia = self
+            .tanks
+            .iter()
+            .map(|tank| {
+                let d = tank.offset - center_of_mass;
+                tank.fuel * (Vec3::splat(d.length_squared()) - d * d)
+            })
+            .sum();
+
+        MassProperties {
+            local_center_of_mass: center_of_mass,
+            mass,
+            principal_inertia_local_frame: Quat::IDENTITY,
+            principal_inertia,
+        }
+    }
+}
+
+pub fn burn_fuel(time: Res<Time>, mut query: Query<(&mut Thrusters, &mut FuelTanks)>) {
+    for (mut thrusters, mut tanks) in query.iter_mut() {
+        let thrusters = thrusters.bypass_change_detection();
+        let required: f32 = thrusters
+            .thrusters
+            .iter()
+            .filter(|thruster| thruster.specific_impulse > 0.0)
+            .map(|thruster| {
+                thruster.output * thruster.delivered_thrust() / (thruster.specific_impulse * G0)
+            })
+            .sum::<f32>()
+            * time.delta_seconds();
+
+        if required <= 0.0 {
+            continue;
+        }
+
+        let available = tanks.fuel();
+        let scale = (available / required).min(1.0);
+
+        if scale < 1.0 {
+            for thruster in &mut thrusters.thrusters {
+                if thruster.specific_impulse > 0.0 {
+                    thruster.output *= scale;
+                }
+            }
+        }
+
+        let burned_fraction = if available > 0.0 {
+            (scale * required / available).min(1.0)
+        } else {
+            0.0
+        };
+
+        for tank in &mut tanks.tanks {
+            tank.fuel -= burned_fraction * tank.fuel;
+        }
+    }
+}
+
+pub fn update_fuel_mass(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut FuelTanks), Changed<FuelTanks>>,
+) {
+    for (entity, mut tanks) in query.iter_mut() {
+        let fuel = tanks.fuel();
+
+        if let Some(applied_fuel) = tanks.applied_fuel {
+            if (applied_fuel - fuel).abs() < MASS_UPDATE_FRACTION * tanks.capacity() {
+                continue;
+            }
+        }
+
+        tanks.applied_fuel = Some(fuel);
+
+        commands
+            .entity(entity)
+            .insert(AdditionalMassProperties::MassProperties(
+                tanks.mass_properties(),
+            ));
+    }
+}
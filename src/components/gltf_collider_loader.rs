@@ -0,0 +1,264 @@
+use super::defer_collider_loader::{
+    bounding_box, compute_collider, ColliderLoadError, ColliderLoadFailed, MeshColliderShape,
+};
+use crate::{
+    assets::{collider_cache::ColliderCache, thruster_mounts::global_node_transforms},
+    entity_label,
+};
+use bevy::{
+    asset::LoadState,
+    gltf::{Gltf, GltfMesh, GltfNode},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum NodeCollider {
+    None,
+    Box,
+    Hull,
+}
+
+#[derive(Default, Deserialize)]
+struct ColliderExtras {
+    collider: Option<NodeCollider>,
+}
+
+#[derive(Debug, Default, Reflect, Component)]
+#[reflect(Component)]
+pub struct GltfColliderLoader {
+    pub gltf: Handle<Gltf>,
+    pub shape: MeshColliderShape,
+}
+
+impl GltfColliderLoader {
+    pub fn new(gltf: Handle<Gltf>, shape: MeshColliderShape) -> Self {
+        Self { gltf, shape }
+    }
+}
+
+#[derive(Default)]
+struct Parts {
+    shapes: Vec<(Vec3, Quat, Collider)>,
+    vertices: Vec<Vec3>,
+    indices: Vec<[u32; 3]>,
+}
+
+impl Parts {
+    fn is_empty(&self) -> bool {
+        self.shapes.is_empty() && self.indices.is_empty()
+    }
+
+    // Parry does not allow composite shapes inside a compound, so they are taken apart here
+    fn push(&mut self, translation: Vec3, rotation: Quat, collider: Collider) {
+        if let Some(compound) = collider.raw.as_compound() {
+            for (isometry, shape) in compound.shapes() {
+                let offset: Vec3 = isometry.translation.vector.into();
+                let orientation: Quat = isometry.rotation.into();
+                self.shapes.push((
+                    translation + rotation * offset,
+                    rotation * orientation,
+                    Collider::from(shape.clone()),
+                ));
+            }
+        } else if let Some(trimesh) = collider.raw.as_trimesh() {
+            let first = self.vertices.len() as u32;
+            self.vertices.extend(
+                trimesh
+                    .vertices()
+                    .iter()
+                    .map(|point| translation + rotation * Vec3::new(point.x, point.y, point.z)),
+            );
+            self.indices.extend(
+                trimesh
+                    .indices()
+                    .iter()
+                    .map(|triangle| triangle.map(|index| first + index)),
+            );
+        } else {
+            self.shapes.push((translation, rotation, collider));
+        }
+    }
+
+    fn collider(mut self, errors: &mut Vec<ColliderLoadError>) -> Option<Collider> {
+        if !self.indices.is_empty() {
+            if self.shapes.is_empty() {
+                return Some(Collider::trimesh(self.vertices, self.indices));
+            }
+
+            errors.push(ColliderLoadError::MixedTriMesh);
+            if let Some(hull) = Collider::convex_hull(&self.vertices) {
+                self.shapes.push((Vec3::ZERO, Quat::IDENTITY, hull));
+            }
+        }
+
+        (!self.shapes.is_empty()).then(|| Collider::compound(self.shapes))
+    }
+}
+
+fn node_collider(node: &GltfNode) -> Option<NodeCollider> {
+    let extras = node.extras.as_ref()?;
+    match serde_json::from_str::<ColliderExtras>(&extras.value) {
+        Ok(extras) => extras.collider,
+        Err(err) => {
+            warn!("Ignoring collider extras of node {}: {err}", node.name);
+            None
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn gltf_collider_loader(
+    mut commands: Commands,
+    mut failures: EventWriter<ColliderLoadFailed>,
+    gltfs: Res<Assets<Gltf>>,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    gltf_meshes: Res<Assets<GltfMesh>>,
+    meshes: Res<Assets<Mesh>>,
+    server: Res<AssetServer>,
+    cache: Res<ColliderCache>,
+    query: Query<(Entity, Option<&Name>, &GltfColliderLoader)>,
+) {
+    'entities: for (entity, name, loader) in query.iter() {
+        let label = entity_label(entity, name);
+
+        let Some(gltf) = gltfs.get(&loader.gltf) else {
+            if let Some(LoadState::Failed(err)) = server.get_load_state(&loader.gltf) {
+                let error = ColliderLoadError::MeshFailed(err.to_string());
+                warn!("Collider for {label}: {error}");
+                failures.send(ColliderLoadFailed { entity, error });
+                commands.entity(entity).remove::<GltfColliderLoader>();
+            }
+            continue;
+        };
+
+        let mut parts = Parts::default();
+        let mut errors = Vec::new();
+        for (node, global_transform) in global_node_transforms(gltf, &gltf_nodes) {
+            let Some(gltf_mesh) = node.mesh.as_ref().and_then(|mesh| gltf_meshes.get(mesh)) else {
+                continue;
+            };
+
+            let shape = match node_collider(node) {
+                Some(NodeCollider::None) => continue,
+                Some(NodeCollider::Box) => None,
+                Some(NodeCollider::Hull) => Some(MeshColliderShape::ConvexHull),
+                None => Some(loader.shape.clone()),
+            };
+
+            let (scale, rotation, translation) = global_transform.to_scale_rotation_translation();
+            for primitive in &gltf_mesh.primitives {
+                let Some(mesh) = meshes.get(&primitive.mesh) else {
+                    continue 'entities;
+                };
+
+                // Scale is baked into the vertices, compound parts only take an isometry.
+                let mesh = mesh.clone().scaled_by(scale);
+                let collider = shape
+                    .as_ref()
+                    .and_then(|shape| compute_collider(&cache, shape, &primitive.mesh, &mesh));
+
+                match (collider, mesh.compute_aabb()) {
+                    (Some(collider), _) => parts.push(translation, rotation, collider),
+                    (None, Some(aabb)) => {
+                        if let Some(shape) = &shape {
+                            errors.push(ColliderLoadError::ShapeFailed(shape.clone()));
+                        }
+                        let (center, cuboid) = bounding_box(&aabb);
+                        parts.push(translation + rotation * center, rotation, cuboid);
+                    }
+                    (None, None) => errors.push(ColliderLoadError::NoVertices),
+                }
+            }
+        }
+
+        if parts.is_empty() {
+            errors.push(ColliderLoadError::NoMeshes);
+        }
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<GltfColliderLoader>();
+        if let Some(collider) = parts.collider(&mut errors) {
+            entity_commands.insert(collider);
+        }
+
+        for error in errors {
+            warn!("Collider for {label}: {error}");
+            failures.send(ColliderLoadFailed { entity, error });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Parts;
+    use crate::components::defer_collider_loader::bounding_box;
+    use bevy::{prelude::*, render::primitives::Aabb};
+    use bevy_rapier3d::prelude::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn offsets(collider: &Collider) -> Vec<Vec3> {
+        let compound = collider
+            .raw
+            .as_compound()
+            .expect("collider should be a compound");
+        compound
+            .shapes()
+            .iter()
+            .map(|(isometry, shape)| {
+                assert!(shape.as_compound().is_none() && shape.as_trimesh().is_none());
+                Vec3::from(isometry.translation.vector)
+            })
+            .collect()
+    }
+
+    fn assert_offsets(collider: &Collider, expected: &[Vec3]) {
+        let offsets = offsets(collider);
+        assert_eq!(offsets.len(), expected.len());
+        for (offset, expected) in offsets.iter().zip(expected) {
+            assert!(
+                (*offset - *expected).length() < 1e-5,
+                "{offset} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn off_center_boxes_are_not_nested() {
+        let mut parts = Parts::default();
+        for x in [-2.0, 3.0] {
+            let aabb = Aabb::from_min_max(Vec3::new(x, 1.0, 0.0), Vec3::new(x + 1.0, 2.0, 1.0));
+            let (center, cuboid) = bounding_box(&aabb);
+            let rotation = Quat::from_rotation_y(FRAC_PI_2);
+            parts.push(Vec3::Z + rotation * center, rotation, cuboid);
+        }
+
+        let mut errors = Vec::new();
+        let collider = parts.collider(&mut errors).unwrap();
+        assert!(errors.is_empty());
+        assert_offsets(
+            &collider,
+            &[Vec3::new(0.5, 1.5, 2.5), Vec3::new(0.5, 1.5, -2.5)],
+        );
+    }
+
+    #[test]
+    fn decomposed_parts_are_flattened() {
+        let cuboid = || Collider::cuboid(0.5, 0.5, 0.5);
+        let decomposition = Collider::compound(vec![
+            (Vec3::X, Quat::IDENTITY, cuboid()),
+            (Vec3::NEG_X, Quat::IDENTITY, cuboid()),
+        ]);
+
+        let mut parts = Parts::default();
+        parts.push(Vec3::Y, Quat::from_rotation_z(FRAC_PI_2), decomposition);
+        parts.push(Vec3::Z, Quat::IDENTITY, cuboid());
+
+        let mut errors = Vec::new();
+        let collider = parts.collider(&mut errors).unwrap();
+        assert!(errors.is_empty());
+        assert_offsets(&collider, &[Vec3::Y * 2.0, Vec3::ZERO, Vec3::Z]);
+    }
+}
@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+
+const ACCELERATION_ITERATIONS: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Intercept {
+    pub point: Vec3,
+    pub time: f32,
+}
+
+fn intercept_time(position: Vec3, velocity: Vec3, projectile_speed: f32) -> Option<f32> {
+    // |position + velocity * t| = projectile_speed * t
+    let a = velocity.length_squared() - projectile_speed * projectile_speed;
+    let b = 2.0 * position.dot(velocity);
+    let c = position.length_squared();
+
+    if a.abs() < f32::EPSILON {
+        // As fast as the projectile, it can only be caught when coming closer
+        return (b < 0.0).then(|| -c / b);
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+    [(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+        .into_iter()
+        .filter(|t| *t > 0.0)
+        .min_by(f32::total_cmp)
+}
+
+pub fn intercept(
+    shooter_position: Vec3,
+    shooter_velocity: Vec3,
+    target_position: Vec3,
+    target_velocity: Vec3,
+    target_acceleration: Vec3,
+    projectile_speed: f32,
+) -> Option<Intercept> {
+    if projectile_speed <= 0.0 {
+        return None;
+    }
+
+    let position = target_position - shooter_position;
+    let velocity = target_velocity - shooter_velocity;
+    let predicted = |t: f32| position + velocity * t + 0.5 * target_acceleration * t * t;
+
+    let mut time = intercept_time(position, velocity, projectile_speed)?;
+
+    if target_acceleration != Vec3::ZERO {
+        // Fixed point of t = |p(t)| / speed, started from the constant velocity solution
+        for _ in 0..ACCELERATION_ITERATIONS {
+            time = predicted(time).length() / projectile_speed;
+        }
+
+        let miss = predicted(time).length() - projectile_speed * time;
+        if !time.is_finite() || miss.abs() > 0.01 * projectile_speed * time.max(1.0) {
+            return None;
+        }
+    }
+
+    Some(Intercept {
+        point: shooter_position + predicted(time),
+        time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::intercept;
+    use bevy::prelude::*;
+
+    #[test]
+    fn stationary_target_is_hit_where_it_is() {
+        let hit = intercept(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::Z * 100.0,
+            Vec3::ZERO,
+            Vec3::ZERO,
+            50.0,
+        )
+        .unwrap();
+        assert!(hit.point.distance(Vec3::Z * 100.0) < 1e-3);
+        assert!((hit.time - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn leads_crossing_target() {
+        let hit = intercept(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::Z * 100.0,
+            Vec3::X * 30.0,
+            Vec3::ZERO,
+            50.0,
+        )
+        .unwrap();
+
+        // 100² + (30t)² = (50t)² gives t = 2.5
+        assert!((hit.time - 2.5).abs() < 1e-3);
+        assert!(hit.point.distance(Vec3::new(75.0, 0.0, 100.0)) < 1e-2);
+    }
+
+    #[test]
+    fn target_outrunning_projectile_cannot_be_hit() {
+        let hit = intercept(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::Z * 100.0,
+            Vec3::Z * 60.0,
+            Vec3::ZERO,
+            50.0,
+        );
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn shooter_velocity_is_inherited() {
+        // Flying alongside the target, it is as good as standing still
+        let hit = intercept(
+            Vec3::ZERO,
+            Vec3::X * 40.0,
+            Vec3::Z * 100.0,
+            Vec3::X * 40.0,
+            Vec3::ZERO,
+            50.0,
+        )
+        .unwrap();
+        assert!(hit.point.distance(Vec3::Z * 100.0) < 1e-3);
+    }
+
+    #[test]
+    fn accelerating_target_is_met() {
+        let acceleration = Vec3::X * 5.0;
+        let hit = intercept(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::Z * 100.0,
+            Vec3::ZERO,
+            acceleration,
+            50.0,
+        )
+        .unwrap();
+
+        let target = Vec3::Z * 100.0 + 0.5 * acceleration * hit.time * hit.time;
+        assert!(hit.point.distance(target) < 1e-2);
+        assert!((hit.point.length() - 50.0 * hit.time).abs() < 1e-2);
+    }
+}
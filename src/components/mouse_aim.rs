@@ -0,0 +1,133 @@
+use super::orientation_regulator::OrientationRegulator;
+use crate::input::{ActionPresses, InputAction, InputMap, InputState};
+use bevy::{
+    input::mouse::MouseMotion,
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+use serde::{Deserialize, Serialize};
+
+const RETICLE_DISTANCE: f32 = 50.0;
+
+#[derive(Component, Clone, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MouseAim {
+    pub enable: bool,
+    /// Radians the reticle moves per pixel of mouse movement.
+    pub sensitivity: f32,
+    /// Largest angle in radians the reticle can get ahead of the nose.
+    pub max_lead: f32,
+    #[serde(skip)]
+    direction: Vec3,
+}
+
+impl Default for MouseAim {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            sensitivity: 0.002,
+            max_lead: 60.0_f32.to_radians(),
+            direction: Vec3::ZERO,
+        }
+    }
+}
+
+impl MouseAim {
+    pub fn direction(&self) -> Vec3 {
+        self.direction
+    }
+}
+
+pub fn toggle_mouse_aim(
+    input_map: Res<InputMap>,
+    input: InputState,
+    mut presses: Local<ActionPresses>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut query: Query<(&mut MouseAim, Option<&mut OrientationRegulator>)>,
+) {
+    if presses.just_pressed(&input_map, &input, InputAction::ToggleMouseAim) {
+        for (mut aim, regulator) in query.iter_mut() {
+            aim.enable = !aim.enable;
+            if let Some(mut regulator) = regulator {
+                regulator.set_enabled(aim.enable);
+            }
+        }
+    }
+
+    let grab = query.iter().any(|(aim, _)| aim.enable);
+    for mut window in windows.iter_mut() {
+        let grab_mode = if grab {
+            CursorGrabMode::Locked
+        } else {
+            CursorGrabMode::None
+        };
+
+        if window.cursor.grab_mode != grab_mode {
+            window.cursor.grab_mode = grab_mode;
+            window.cursor.visible = !grab;
+        }
+    }
+}
+
+pub fn mouse_aim(
+    mut motion: EventReader<MouseMotion>,
+    mut query: Query<(&Transform, &mut MouseAim, &mut OrientationRegulator)>,
+) {
+    let delta: Vec2 = motion.read().map(|motion| motion.delta).sum();
+
+    for (transform, mut aim, mut regulator) in query.iter_mut() {
+        if !aim.enable {
+            aim.direction = Vec3::ZERO;
+            continue;
+        }
+
+        if !regulator.enabled() {
+            regulator.set_enabled(true);
+        }
+
+        let forward = *transform.forward();
+        if aim.direction == Vec3::ZERO {
+            aim.direction = forward;
+        }
+
+        let yaw = Quat::from_axis_angle(*transform.up(), -delta.x * aim.sensitivity);
+        let pitch = Quat::from_axis_angle(*transform.right(), -delta.y * aim.sensitivity);
+        let mut direction = (yaw * pitch * aim.direction).normalize();
+
+        if forward.angle_between(direction) > aim.max_lead {
+            if let Some(axis) = forward.cross(direction).try_normalize() {
+                direction = Quat::from_axis_angle(axis, aim.max_lead) * forward;
+            }
+        }
+
+        aim.direction = direction;
+
+        regulator.update_target(
+            Transform::IDENTITY
+                .looking_to(direction, *transform.up())
+                .rotation,
+        );
+    }
+}
+
+pub fn draw_aim_reticle(mut gizmos: Gizmos, query: Query<(&Transform, &MouseAim)>) {
+    for (transform, aim) in query.iter() {
+        let Ok(direction) = Dir3::new(aim.direction) else {
+            continue;
+        };
+
+        gizmos.circle(
+            transform.translation + RETICLE_DISTANCE * *direction,
+            -direction,
+            1.0,
+            Color::srgb(0.2, 1.0, 0.4),
+        );
+        gizmos.circle(
+            transform.translation + RETICLE_DISTANCE * *transform.forward(),
+            -transform.forward(),
+            0.4,
+            Color::WHITE,
+        );
+    }
+}
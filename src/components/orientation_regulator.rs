@@ -1,19 +1,36 @@
-use super::{
-    max_torque::MaxTorque,
-    thrusters::{ThrusterGroup, Thrusters},
-};
+use super::{control_authority::ControlAuthority, thrust_allocation::Wrench, thrusters::Thrusters};
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Component, Reflect, Serialize, Deserialize)]
+const SLEW_MARGIN: f32 = 0.8;
+
+const SETTLING_RATE: f32 = 4.0;
+
+const ANGACC_SMOOTHING: f32 = 0.3;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub enum RegulatorMode {
+    #[default]
+    Euler,
+    Quaternion,
+}
+
+#[derive(Component, Clone, Reflect, Serialize, Deserialize)]
 #[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OrientationRegulator {
     target: Quat,
     target_angvel: Vec3,
-    #[serde(skip_serializing)]
+    #[serde(skip)]
     local_angvel: Vec3,
+    #[serde(skip)]
+    local_angacc: Vec3,
     p_gain: f32,
+    mode: RegulatorMode,
+    /// Regulate on the state predicted one thruster response time ahead.
+    lag_compensation: bool,
     enable: bool,
 }
 
@@ -23,7 +40,10 @@ impl Default for OrientationRegulator {
             target: Quat::from_euler(EulerRot::XYZ, 0.0, 0.0, 0.0),
             target_angvel: Default::default(),
             local_angvel: Default::default(),
+            local_angacc: Default::default(),
             p_gain: 10.0,
+            mode: RegulatorMode::Euler,
+            lag_compensation: false,
             enable: true,
         }
     }
@@ -33,6 +53,32 @@ impl OrientationRegulator {
     pub fn update_target(&mut self, target: Quat) {
         self.target = target;
     }
+
+    pub fn enabled(&self) -> bool {
+        self.enable
+    }
+
+    pub fn set_enabled(&mut self, enable: bool) {
+        self.enable = enable;
+    }
+
+    pub fn reconfigure(&mut self, definition: Self) {
+        self.p_gain = definition.p_gain;
+        self.mode = definition.mode;
+        self.lag_compensation = definition.lag_compensation;
+    }
+
+    pub fn target(&self) -> Quat {
+        self.target
+    }
+
+    pub fn target_angvel(&self) -> Vec3 {
+        self.target_angvel
+    }
+
+    pub fn local_angvel(&self) -> Vec3 {
+        self.local_angvel
+    }
 }
 
 fn calculate_target_angular_velocity(
@@ -91,52 +137,222 @@ fn calculate_target_angular_velocity(
     }
 }
 
+fn local_inertia(mass_props: &MassProperties) -> Vec3 {
+    // Diagonal of the inertia tensor in the ship's axes
+    let frame = Mat3::from_quat(mass_props.principal_inertia_local_frame);
+    Vec3::from_array(std::array::from_fn(|i| {
+        let row = frame.row(i);
+        (row * row).dot(mass_props.principal_inertia)
+    }))
+}
+
+fn calculate_target_slew(remaining: Quat, max_torque: Vec3, mass_props: &MassProperties) -> Vec3 {
+    // q and -q are the same rotation, the one with positive w is the short way around
+    let remaining = if remaining.w < 0.0 {
+        -remaining
+    } else {
+        remaining
+    };
+
+    let (axis, angle) = remaining.to_axis_angle();
+    if angle <= f32::EPSILON {
+        return Vec3::ZERO;
+    }
+
+    // The inertia is given around the principal axes, which need not be the ship's axes
+    let frame = mass_props.principal_inertia_local_frame;
+    let torque_per_acceleration = frame * (mass_props.principal_inertia * (frame.inverse() * axis));
+
+    let mut angular_acceleration = f32::INFINITY;
+    for i in 0..3 {
+        if torque_per_acceleration[i].abs() > f32::EPSILON {
+            angular_acceleration =
+                angular_acceleration.min(max_torque[i] / torque_per_acceleration[i].abs());
+        }
+    }
+
+    if !angular_acceleration.is_finite() {
+        return Vec3::ZERO;
+    }
+
+    let slew_rate = (2.0 * SLEW_MARGIN * angular_acceleration * angle)
+        .sqrt()
+        .min(SETTLING_RATE * angle);
+
+    slew_rate * axis
+}
+
 pub fn orientation_regulator(
+    time: Res<Time>,
     mut query: Query<(
         &Transform,
         &Velocity,
         &ReadMassProperties,
-        &MaxTorque,
+        &ControlAuthority,
         &mut Thrusters,
         &mut OrientationRegulator,
     )>,
 ) {
-    for (transform, vel, mass_props, max_torque, mut thrusters, mut regulator) in query.iter_mut() {
-        regulator.local_angvel = transform.rotation.inverse().mul_vec3(vel.angvel);
+    for (transform, vel, mass_props, authority, mut thrusters, mut regulator) in query.iter_mut() {
+        let thrusters = thrusters.bypass_change_detection();
+        let local_angvel = transform.rotation.inverse().mul_vec3(vel.angvel);
+        if time.delta_seconds() > 0.0 {
+            let angacc = (local_angvel - regulator.local_angvel) / time.delta_seconds();
+            regulator.local_angacc = regulator.local_angacc.lerp(angacc, ANGACC_SMOOTHING);
+        }
+        regulator.local_angvel = local_angvel;
+
         if regulator.enable {
-            let remaning_angle = Vec3::from(
-                (transform.rotation.inverse() * regulator.target).to_euler(EulerRot::XYZ),
-            );
+            let (rotation, angvel) = if regulator.lag_compensation {
+                let lag = thrusters.rotation_response_time(mass_props.get().local_center_of_mass);
+                let angvel = local_angvel + lag * regulator.local_angacc;
+                (
+                    transform.rotation * Quat::from_scaled_axis(lag * local_angvel),
+                    angvel,
+                )
+            } else {
+                (transform.rotation, local_angvel)
+            };
 
-            for axis in 0..3 {
-                regulator.target_angvel[axis] = calculate_target_angular_velocity(
-                    remaning_angle[axis],
-                    regulator.local_angvel[axis],
-                    max_torque.positive_torque[axis].min(max_torque.negative_torque[axis]),
-                    mass_props.get().principal_inertia[axis],
-                );
-            }
+            let remaining = rotation.inverse() * regulator.target;
+            let symmetric_torque = authority.positive_torque.min(authority.negative_torque);
 
-            let mut groups_to_fire = ThrusterGroup::NONE;
-            let error = regulator.target_angvel - regulator.local_angvel;
+            match regulator.mode {
+                RegulatorMode::Euler => {
+                    let angular_inertia = local_inertia(mass_props.get());
+                    let remaning_angle = Vec3::from(remaining.to_euler(EulerRot::XYZ));
 
-            let error_abs = error.abs();
+                    for axis in 0..3 {
+                        regulator.target_angvel[axis] = calculate_target_angular_velocity(
+                            remaning_angle[axis],
+                            angvel[axis],
+                            symmetric_torque[axis],
+                            angular_inertia[axis],
+                        );
+                    }
+                }
+                RegulatorMode::Quaternion => {
+                    regulator.target_angvel =
+                        calculate_target_slew(remaining, symmetric_torque, mass_props.get());
+                }
+            }
 
-            let thrust = regulator.p_gain * error_abs;
+            let error = regulator.target_angvel - angvel;
 
+            let command = (regulator.p_gain * error).clamp(Vec3::NEG_ONE, Vec3::ONE);
+
+            let mut torque = Vec3::ZERO;
             for axis in 0..3 {
-                if error_abs[axis] > 0.0 {
-                    let group = if error[axis] > 0.0 {
-                        ThrusterGroup::positive_rotation(axis)
-                    } else {
-                        ThrusterGroup::negative_rotation(axis)
-                    };
-                    groups_to_fire |= group;
-                    thrusters.group_thrust[group.index()] = thrust[axis];
-                }
+                torque[axis] = if command[axis] > 0.0 {
+                    command[axis] * authority.positive_torque[axis]
+                } else {
+                    command[axis] * authority.negative_torque[axis]
+                };
             }
 
-            thrusters.groups_to_fire |= groups_to_fire;
+            thrusters.wrench += Wrench::from_torque(torque);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calculate_target_angular_velocity, calculate_target_slew, SLEW_MARGIN};
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::MassProperties;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn mass_props(principal_inertia: Vec3, frame: Quat) -> MassProperties {
+        MassProperties {
+            principal_inertia,
+            principal_inertia_local_frame: frame,
+            ..Default::default()
         }
     }
+
+    #[test]
+    fn zero_inertia_keeps_angular_velocity() {
+        assert_eq!(calculate_target_angular_velocity(1.0, 0.5, 10.0, 0.0), 0.5);
+        assert_eq!(
+            calculate_target_angular_velocity(-1.0, -2.0, 10.0, 0.0),
+            -2.0
+        );
+    }
+
+    #[test]
+    fn accelerates_towards_target_from_rest() {
+        assert_eq!(calculate_target_angular_velocity(1.0, 0.0, 1.0, 1.0), 10.0);
+        assert_eq!(
+            calculate_target_angular_velocity(-1.0, 0.0, 1.0, 1.0),
+            -10.0
+        );
+    }
+
+    #[test]
+    fn keeps_accelerating_while_far_from_target() {
+        assert_eq!(calculate_target_angular_velocity(10.0, 1.0, 1.0, 1.0), 10.0);
+        assert_eq!(
+            calculate_target_angular_velocity(-10.0, -1.0, 1.0, 1.0),
+            -10.0
+        );
+    }
+
+    #[test]
+    fn brakes_when_it_cannot_stop_in_time() {
+        assert_eq!(calculate_target_angular_velocity(0.1, 2.0, 1.0, 1.0), -10.0);
+        assert_eq!(
+            calculate_target_angular_velocity(-0.1, -2.0, 1.0, 1.0),
+            10.0
+        );
+    }
+
+    #[test]
+    fn turns_around_when_moving_away_from_target() {
+        assert_eq!(calculate_target_angular_velocity(1.0, -1.0, 1.0, 1.0), 10.0);
+        assert_eq!(
+            calculate_target_angular_velocity(-1.0, 3.0, 1.0, 1.0),
+            -10.0
+        );
+    }
+
+    #[test]
+    fn is_symmetric_in_direction() {
+        for (angle, angular_velocity) in [(0.5, 0.3), (2.0, -1.5), (0.05, 1.0), (3.0, 4.0)] {
+            assert_eq!(
+                calculate_target_angular_velocity(angle, angular_velocity, 2.0, 3.0),
+                -calculate_target_angular_velocity(-angle, -angular_velocity, 2.0, 3.0),
+            );
+        }
+    }
+
+    #[test]
+    fn slews_the_short_way_around() {
+        let mass_props = mass_props(Vec3::ONE, Quat::IDENTITY);
+        let remaining = Quat::from_rotation_y(FRAC_PI_2);
+
+        let slew = calculate_target_slew(remaining, Vec3::ONE, &mass_props);
+        assert!(slew.y > 0.0 && slew.x == 0.0 && slew.z == 0.0, "{slew}");
+        assert!((calculate_target_slew(-remaining, Vec3::ONE, &mass_props) - slew).length() < 1e-5);
+    }
+
+    #[test]
+    fn slews_half_a_turn() {
+        let mass_props = mass_props(Vec3::ONE, Quat::IDENTITY);
+
+        let slew = calculate_target_slew(Quat::from_rotation_y(PI), Vec3::ONE, &mass_props);
+        let expected = (2.0 * SLEW_MARGIN * PI).sqrt();
+        assert!((slew.y.abs() - expected).abs() < 1e-3, "{slew}");
+        assert!(slew.x.abs() < 1e-3 && slew.z.abs() < 1e-3, "{slew}");
+    }
+
+    #[test]
+    fn slew_uses_the_principal_frame() {
+        let remaining = Quat::from_rotation_x(0.5);
+        let swapped = mass_props(Vec3::new(1.0, 4.0, 1.0), Quat::from_rotation_z(FRAC_PI_2));
+        let aligned = mass_props(Vec3::new(4.0, 1.0, 1.0), Quat::IDENTITY);
+
+        let slew = calculate_target_slew(remaining, Vec3::ONE, &swapped);
+        let expected = calculate_target_slew(remaining, Vec3::ONE, &aligned);
+        assert!((slew - expected).length() < 1e-4, "{slew} != {expected}");
+    }
 }
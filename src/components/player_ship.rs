@@ -1,4 +1,10 @@
-use super::thrusters::{ThrusterGroup, Thrusters};
+use super::{
+    control_authority::ControlAuthority,
+    mouse_aim::MouseAim,
+    thrusters::{ThrusterGroup, Thrusters},
+    weapons::Weapons,
+};
+use crate::input::{apply_input_map, InputAction, InputMap, InputState};
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -7,60 +13,37 @@ use serde::{Deserialize, Serialize};
 pub struct PlayerShip;
 
 pub fn player_thrusters(
-    mut query: Query<(&PlayerShip, &mut Thrusters)>,
-    keyboard: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(&ControlAuthority, Option<&MouseAim>, &mut Thrusters), With<PlayerShip>>,
+    input_map: Res<InputMap>,
+    input: InputState,
 ) {
-    let mut groups_to_fire = ThrusterGroup::NONE;
-
-    if keyboard.pressed(KeyCode::KeyW) {
-        groups_to_fire |= ThrusterGroup::FORWARD;
-    }
-
-    if keyboard.pressed(KeyCode::KeyS) {
-        groups_to_fire |= ThrusterGroup::BACKWARD;
-    }
-
-    if keyboard.pressed(KeyCode::KeyD) {
-        groups_to_fire |= ThrusterGroup::RIGHT;
-    }
-
-    if keyboard.pressed(KeyCode::KeyA) {
-        groups_to_fire |= ThrusterGroup::LEFT;
-    }
-
-    if keyboard.pressed(KeyCode::Space) {
-        groups_to_fire |= ThrusterGroup::UP;
-    }
-
-    if keyboard.pressed(KeyCode::ShiftLeft) {
-        groups_to_fire |= ThrusterGroup::DOWN;
-    }
-
-    if keyboard.pressed(KeyCode::Numpad6) {
-        groups_to_fire |= ThrusterGroup::NYROT;
-    }
-
-    if keyboard.pressed(KeyCode::Numpad4) {
-        groups_to_fire |= ThrusterGroup::YROT;
-    }
-
-    if keyboard.pressed(KeyCode::Numpad8) {
-        groups_to_fire |= ThrusterGroup::NXROT;
-    }
-
-    if keyboard.pressed(KeyCode::Numpad5) {
-        groups_to_fire |= ThrusterGroup::XROT;
-    }
-
-    if keyboard.pressed(KeyCode::Numpad9) {
-        groups_to_fire |= ThrusterGroup::NZROT;
+    for (authority, aim, mut thrusters) in query.iter_mut() {
+        let ignored = if aim.is_some_and(|aim| aim.enable) {
+            ThrusterGroup::XROT | ThrusterGroup::NXROT | ThrusterGroup::YROT | ThrusterGroup::NYROT
+        } else {
+            ThrusterGroup::NONE
+        };
+
+        apply_input_map(
+            &input_map,
+            &input,
+            authority,
+            ignored,
+            thrusters.bypass_change_detection(),
+        );
     }
+}
 
-    if keyboard.pressed(KeyCode::Numpad7) {
-        groups_to_fire |= ThrusterGroup::ZROT;
+pub fn player_weapons(
+    mut query: Query<&mut Weapons, With<PlayerShip>>,
+    input_map: Res<InputMap>,
+    input: InputState,
+) {
+    if input_map.action_value(&input, InputAction::Fire) <= 0.5 {
+        return;
     }
 
-    for (_, mut thrusters) in query.iter_mut() {
-        thrusters.groups_to_fire |= groups_to_fire;
+    for mut weapons in query.iter_mut() {
+        weapons.trigger = true;
     }
 }
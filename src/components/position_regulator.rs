@@ -0,0 +1,101 @@
+use super::{
+    control_authority::ControlAuthority,
+    thrusters::ThrusterGroup,
+    velocity_regulator::{max_acceleration, VelocityRegulator},
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const BRAKING_MARGIN: f32 = 0.8;
+
+#[derive(Component, Clone, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PositionRegulator {
+    target: Vec3,
+    #[serde(skip)]
+    target_velocity: Vec3,
+    max_speed: f32,
+    p_gain: f32,
+    enable: bool,
+}
+
+impl Default for PositionRegulator {
+    fn default() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            target_velocity: Vec3::ZERO,
+            max_speed: 20.0,
+            p_gain: 1.0,
+            enable: false,
+        }
+    }
+}
+
+impl PositionRegulator {
+    pub fn reconfigure(&mut self, definition: Self) {
+        self.max_speed = definition.max_speed;
+        self.p_gain = definition.p_gain;
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enable
+    }
+}
+
+fn calculate_target_velocity(
+    remaining_distance: f32,
+    braking_acceleration: f32,
+    max_speed: f32,
+    p_gain: f32,
+) -> f32 {
+    let distance = remaining_distance.abs();
+
+    let stopping_speed = (2.0 * braking_acceleration * distance).sqrt();
+
+    remaining_distance.signum() * stopping_speed.min(p_gain * distance).min(max_speed)
+}
+
+pub fn position_regulator(
+    mut query: Query<(
+        &Transform,
+        &ReadMassProperties,
+        &ControlAuthority,
+        &mut PositionRegulator,
+        &mut VelocityRegulator,
+    )>,
+) {
+    for (transform, mass_props, authority, mut regulator, mut velocity_regulator) in
+        query.iter_mut()
+    {
+        if regulator.enable && velocity_regulator.enabled() {
+            let mass = mass_props.get().mass;
+            let remaining_distance = transform
+                .rotation
+                .inverse()
+                .mul_vec3(regulator.target - transform.translation);
+
+            let mut local_target_velocity = Vec3::ZERO;
+
+            for axis in 0..3 {
+                // Moving towards a positive remaining distance is braked by the negative group
+                let braking_group = if remaining_distance[axis] > 0.0 {
+                    ThrusterGroup::negative_translation(axis)
+                } else {
+                    ThrusterGroup::positive_translation(axis)
+                };
+
+                local_target_velocity[axis] = calculate_target_velocity(
+                    remaining_distance[axis],
+                    BRAKING_MARGIN * max_acceleration(authority, braking_group, axis, mass),
+                    regulator.max_speed,
+                    regulator.p_gain,
+                );
+            }
+
+            regulator.target_velocity = transform.rotation.mul_vec3(local_target_velocity);
+            velocity_regulator.update_target(regulator.target_velocity);
+        }
+    }
+}
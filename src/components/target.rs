@@ -1,18 +1,265 @@
-use super::orientation_regulator::OrientationRegulator;
+use super::{
+    intercept::intercept, mouse_aim::MouseAim, orientation_regulator::OrientationRegulator,
+    player_ship::PlayerShip,
+};
+use crate::input::{ActionPresses, InputAction, InputMap, InputState};
 use bevy::{math::Vec3, prelude::*};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Largest angle in radians between the reticle and a target for it to be picked by the
+/// in-reticle command.
+const RETICLE_CONE: f32 = 0.17;
+
+const ACCELERATION_SMOOTHING: f32 = 0.2;
 
 #[derive(Component)]
 pub struct Target;
 
+#[derive(Component, Clone, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Targeting {
+    #[serde(skip)]
+    selected: Option<Entity>,
+    pub lock: bool,
+    pub projectile_speed: Option<f32>,
+    #[serde(skip)]
+    lead_point: Option<Vec3>,
+    #[serde(skip)]
+    target_velocity: Option<Vec3>,
+    #[serde(skip)]
+    target_acceleration: Vec3,
+}
+
+impl Default for Targeting {
+    fn default() -> Self {
+        Self {
+            selected: None,
+            lock: true,
+            projectile_speed: None,
+            lead_point: None,
+            target_velocity: None,
+            target_acceleration: Vec3::ZERO,
+        }
+    }
+}
+
+impl Targeting {
+    pub fn locked(target: Entity) -> Self {
+        Self {
+            selected: Some(target),
+            ..Default::default()
+        }
+    }
+
+    pub fn selected(&self) -> Option<Entity> {
+        self.selected
+    }
+
+    pub fn select(&mut self, target: Option<Entity>) {
+        if target != self.selected {
+            self.selected = target;
+            self.lead_point = None;
+            self.target_velocity = None;
+            self.target_acceleration = Vec3::ZERO;
+        }
+    }
+
+    pub fn lead_point(&self) -> Option<Vec3> {
+        self.lead_point
+    }
+
+    pub fn following(&self) -> bool {
+        self.lock && self.selected.is_some()
+    }
+}
+
+pub fn validate_targets(mut query: Query<&mut Targeting>, targets: Query<(), With<Target>>) {
+    for mut targeting in query.iter_mut() {
+        if targeting
+            .selected
+            .is_some_and(|target| !targets.contains(target))
+        {
+            targeting.select(None);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
 pub fn target_update_system(
-    targets: Query<&Transform, With<Target>>,
-    mut regulators: Query<(&mut OrientationRegulator, &Transform)>,
+    time: Res<Time>,
+    targets: Query<(&Transform, Option<&Velocity>), With<Target>>,
+    mut ships: Query<(
+        &mut Targeting,
+        Option<&mut OrientationRegulator>,
+        &Transform,
+        Option<&Velocity>,
+        Option<&MouseAim>,
+    )>,
+) {
+    let dt = time.delta_seconds();
+
+    for (mut targeting, regulator, source_transform, source_velocity, aim) in ships.iter_mut() {
+        let targeting = targeting.as_mut();
+
+        let Some((target_transform, target_velocity)) = targeting
+            .selected
+            .and_then(|target| targets.get(target).ok())
+        else {
+            targeting.lead_point = None;
+            continue;
+        };
+
+        let target_velocity = target_velocity.map_or(Vec3::ZERO, |velocity| velocity.linvel);
+        if let Some(previous) = targeting.target_velocity.filter(|_| dt > 0.0) {
+            let acceleration = (target_velocity - previous) / dt;
+            targeting.target_acceleration = targeting
+                .target_acceleration
+                .lerp(acceleration, ACCELERATION_SMOOTHING);
+        }
+        targeting.target_velocity = Some(target_velocity);
+
+        targeting.lead_point = targeting.projectile_speed.and_then(|speed| {
+            intercept(
+                source_transform.translation,
+                source_velocity.map_or(Vec3::ZERO, |velocity| velocity.linvel),
+                target_transform.translation,
+                target_velocity,
+                targeting.target_acceleration,
+                speed,
+            )
+            .map(|intercept| intercept.point)
+        });
+
+        let Some(mut regulator) = regulator else {
+            continue;
+        };
+
+        if !targeting.lock || aim.is_some_and(|aim| aim.enable) {
+            continue;
+        }
+
+        let aim_point = targeting.lead_point.unwrap_or(target_transform.translation);
+
+        let direction = (aim_point - source_transform.translation).normalize_or_zero();
+        if direction == Vec3::ZERO {
+            continue;
+        }
+
+        // Looking straight along up has no roll to keep, pitching over the nose does
+        let up = if direction.dot(*source_transform.up()).abs() > 0.999 {
+            *source_transform.forward()
+        } else {
+            *source_transform.up()
+        };
+
+        let goal_transform = source_transform.looking_to(direction, up);
+        regulator.update_target(goal_transform.rotation);
+        regulator.set_enabled(true);
+    }
+}
+
+pub fn select_targets(
+    input_map: Res<InputMap>,
+    input: InputState,
+    mut presses: Local<ActionPresses>,
+    mut ships: Query<(&Transform, &mut Targeting, Option<&MouseAim>), With<PlayerShip>>,
+    targets: Query<(Entity, &Transform), With<Target>>,
 ) {
-    for (mut regulator, source_transform) in regulators.iter_mut() {
-        for target_transform in targets.iter() {
-            let goal_transform =
-                source_transform.looking_at(target_transform.translation, Vec3::ZERO);
-            regulator.update_target(goal_transform.rotation);
+    let mut just_pressed = |action| presses.just_pressed(&input_map, &input, action);
+    let next = just_pressed(InputAction::NextTarget);
+    let previous = just_pressed(InputAction::PreviousTarget);
+    let nearest = just_pressed(InputAction::NearestTarget);
+    let in_reticle = just_pressed(InputAction::ReticleTarget);
+    let toggle_lock = just_pressed(InputAction::ToggleTargetLock);
+
+    let mut sorted: Vec<(Entity, Vec3)> = targets
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation))
+        .collect();
+    sorted.sort_by_key(|(entity, _)| *entity);
+
+    for (transform, mut targeting, aim) in ships.iter_mut() {
+        let current = targeting
+            .selected
+            .and_then(|selected| sorted.iter().position(|(entity, _)| *entity == selected));
+
+        let selected = if next {
+            let next = current.map_or(0, |i| i + 1);
+            sorted
+                .get(next % sorted.len().max(1))
+                .map(|(entity, _)| *entity)
+        } else if previous {
+            let previous = current.unwrap_or(0) + sorted.len().max(1) - 1;
+            sorted
+                .get(previous % sorted.len().max(1))
+                .map(|(entity, _)| *entity)
+        } else if nearest {
+            sorted
+                .iter()
+                .min_by(|(_, a), (_, b)| {
+                    a.distance_squared(transform.translation)
+                        .total_cmp(&b.distance_squared(transform.translation))
+                })
+                .map(|(entity, _)| *entity)
+        } else if in_reticle {
+            let aim = aim
+                .filter(|aim| aim.enable && aim.direction() != Vec3::ZERO)
+                .map_or(*transform.forward(), MouseAim::direction);
+
+            sorted
+                .iter()
+                .map(|(entity, position)| {
+                    (
+                        *entity,
+                        aim.angle_between(*position - transform.translation),
+                    )
+                })
+                .filter(|(_, angle)| *angle <= RETICLE_CONE)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(entity, _)| entity)
+                .or(targeting.selected)
+        } else {
+            targeting.selected
+        };
+
+        if selected != targeting.selected {
+            targeting.select(selected);
+        }
+
+        if toggle_lock {
+            targeting.lock = !targeting.lock;
+        }
+    }
+}
+
+pub fn draw_selected_targets(
+    mut gizmos: Gizmos,
+    ships: Query<&Targeting, With<PlayerShip>>,
+    targets: Query<&GlobalTransform, With<Target>>,
+) {
+    for targeting in ships.iter() {
+        let Some(transform) = targeting
+            .selected
+            .and_then(|target| targets.get(target).ok())
+        else {
+            continue;
+        };
+
+        let color = if targeting.lock {
+            Color::srgb(1.0, 0.3, 0.2)
+        } else {
+            Color::srgb(1.0, 0.9, 0.2)
+        };
+        gizmos.cuboid(
+            Transform::from_translation(transform.translation()).with_scale(Vec3::splat(2.0)),
+            color,
+        );
+
+        if let Some(lead_point) = targeting.lead_point {
+            gizmos.line(transform.translation(), lead_point, color);
+            gizmos.sphere(lead_point, Quat::IDENTITY, 0.5, color);
         }
     }
 }
@@ -0,0 +1,401 @@
+use super::{
+    control_authority::ControlAuthority,
+    thruster_failures::ThrusterFailure,
+    thrusters::{ThrusterGroup, Thrusters},
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::ReadMassProperties;
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign, Mul, Sub};
+
+const SOLVER_ITERATIONS: usize = 64;
+const FUEL_WEIGHT: f32 = 0.001;
+const AUTHORITY_THRESHOLD: f32 = 1e-4;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub struct Wrench {
+    pub force: Vec3,
+    pub torque: Vec3,
+}
+
+impl Wrench {
+    pub const ZERO: Wrench = Wrench {
+        force: Vec3::ZERO,
+        torque: Vec3::ZERO,
+    };
+
+    pub fn new(force: Vec3, torque: Vec3) -> Self {
+        Self { force, torque }
+    }
+
+    pub fn from_torque(torque: Vec3) -> Self {
+        Self::new(Vec3::ZERO, torque)
+    }
+
+    fn to_array(self) -> [f32; 6] {
+        [
+            self.force.x,
+            self.force.y,
+            self.force.z,
+            self.torque.x,
+            self.torque.y,
+            self.torque.z,
+        ]
+    }
+}
+
+impl Add for Wrench {
+    type Output = Wrench;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Wrench::new(self.force + rhs.force, self.torque + rhs.torque)
+    }
+}
+
+impl AddAssign for Wrench {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Wrench {
+    type Output = Wrench;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Wrench::new(self.force - rhs.force, self.torque - rhs.torque)
+    }
+}
+
+impl Mul<f32> for Wrench {
+    type Output = Wrench;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Wrench::new(self.force * rhs, self.torque * rhs)
+    }
+}
+
+pub fn solve_allocation(
+    columns: &[Wrench],
+    costs: &[f32],
+    target: Wrench,
+    upper: &[f32],
+) -> Vec<f32> {
+    let n = columns.len();
+
+    let mut scale = [0.0; 6];
+    for column in columns {
+        for (s, c) in scale.iter_mut().zip(column.to_array()) {
+            *s += c.abs();
+        }
+    }
+    // Rounding in the thruster rotations leaves tiny authority on other axes
+    let threshold = scale.iter().copied().fold(0.0, f32::max) * AUTHORITY_THRESHOLD;
+    for s in &mut scale {
+        *s = if *s > threshold.max(f32::EPSILON) {
+            1.0 / *s
+        } else {
+            0.0
+        };
+    }
+
+    let a: Vec<[f32; 6]> = columns
+        .iter()
+        .map(|column| {
+            let column = column.to_array();
+            std::array::from_fn(|k| column[k] * scale[k])
+        })
+        .collect();
+
+    let target = target.to_array();
+    let target: [f32; 6] = std::array::from_fn(|k| target[k] * scale[k]);
+
+    // The gradient of |Au - t|² is Lipschitz with 2 * the largest eigenvalue of A Aᵀ
+    let mut aat = [[0.0; 6]; 6];
+    for column in &a {
+        for i in 0..6 {
+            for j in 0..6 {
+                aat[i][j] += column[i] * column[j];
+            }
+        }
+    }
+
+    let mut v = [1.0; 6];
+    let mut eigenvalue = 0.0;
+    for _ in 0..16 {
+        let w: [f32; 6] = std::array::from_fn(|i| (0..6).map(|j| aat[i][j] * v[j]).sum());
+        let norm = w.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm <= f32::EPSILON {
+            break;
+        }
+        eigenvalue = norm / v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        v = w.map(|x| x / norm);
+    }
+
+    let mut x = vec![0.0; n];
+    if eigenvalue <= f32::EPSILON {
+        return x;
+    }
+
+    let step = 1.0 / (2.0 * eigenvalue);
+    let mut y = x.clone();
+    let mut t = 1.0f32;
+
+    for _ in 0..SOLVER_ITERATIONS {
+        let mut residual = target.map(|t| -t);
+        for (column, y) in a.iter().zip(&y) {
+            for k in 0..6 {
+                residual[k] += column[k] * y;
+            }
+        }
+
+        let x_next: Vec<f32> = (0..n)
+            .map(|i| {
+                let gradient = 2.0 * (0..6).map(|k| a[i][k] * residual[k]).sum::<f32>()
+                    + FUEL_WEIGHT * costs[i];
+                (y[i] - step * gradient).clamp(0.0, upper[i])
+            })
+            .collect();
+
+        let t_next = 0.5 * (1.0 + (1.0 + 4.0 * t * t).sqrt());
+        let momentum = (t - 1.0) / t_next;
+
+        for i in 0..n {
+            y[i] = x_next[i] + momentum * (x_next[i] - x[i]);
+        }
+
+        x = x_next;
+        t = t_next;
+    }
+
+    x
+}
+
+pub fn allocate_thrust(mut query: Query<(&mut Thrusters, &ControlAuthority, &ReadMassProperties)>) {
+    for (mut thrusters, authority, mass_props) in query.iter_mut() {
+        let thrusters = thrusters.bypass_change_detection();
+        let center_of_mass = mass_props.get().local_center_of_mass;
+
+        let requested = ThrusterGroup::NAMES
+            .iter()
+            .filter(|(_, group)| thrusters.groups_to_fire.intersects(*group))
+            .fold(thrusters.wrench, |requested, (_, group)| {
+                let magnitude = thrusters.group_thrust[group.index()];
+                let magnitude = if magnitude == 0.0 {
+                    1.0
+                } else {
+                    magnitude.clamp(0.0, 1.0)
+                };
+                requested + authority.group_wrench(*group, magnitude)
+            });
+
+        let mut throttle = vec![0.0; thrusters.thrusters.len()];
+
+        // Thrusters stuck on push the ship around no matter what, the others have to cancel it
+        let disturbance = thrusters
+            .thrusters
+            .iter()
+            .filter(|thruster| thruster.failure == ThrusterFailure::StuckOn)
+            .fold(Wrench::ZERO, |disturbance, thruster| {
+                disturbance
+                    + Wrench::new(
+                        thruster.local_force(),
+                        thruster.local_torque(center_of_mass),
+                    )
+            });
+
+        let target = requested - disturbance;
+
+        if target != Wrench::ZERO {
+            let columns: Vec<Wrench> = thrusters
+                .thrusters
+                .iter()
+                .map(|thruster| {
+                    Wrench::new(
+                        thruster.local_force(),
+                        thruster.local_torque(center_of_mass),
+                    ) * thruster.failure.controllable_fraction()
+                })
+                .collect();
+
+            let max_thrust = thrusters
+                .thrusters
+                .iter()
+                .map(|thruster| thruster.thrust)
+                .fold(f32::EPSILON, f32::max);
+
+            let costs: Vec<f32> = thrusters
+                .thrusters
+                .iter()
+                .map(|thruster| thruster.thrust / max_thrust)
+                .collect();
+
+            let upper: Vec<f32> = thrusters
+                .thrusters
+                .iter()
+                .map(|thruster| {
+                    if thruster.failure.controllable_fraction() > 0.0 {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                })
+                .collect();
+
+            throttle = solve_allocation(&columns, &costs, target, &upper);
+        }
+
+        thrusters.throttle = throttle;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{allocate_thrust, solve_allocation, Wrench};
+    use crate::components::{
+        control_authority::{update_control_authority, ControlAuthority},
+        thruster_failures::ThrusterFailure,
+        thrusters::{Thruster, ThrusterGroup, Thrusters},
+    };
+    use bevy::{ecs::system::RunSystemOnce, prelude::*};
+    use bevy_rapier3d::prelude::ReadMassProperties;
+    use std::f32::consts::PI;
+
+    fn force(direction: Vec3) -> Wrench {
+        Wrench::new(direction, Vec3::ZERO)
+    }
+
+    #[test]
+    fn achievable_wrench_is_matched() {
+        let columns = [
+            force(Vec3::X * 10.0),
+            force(Vec3::NEG_X * 10.0),
+            force(Vec3::Z * 10.0),
+            force(Vec3::NEG_Z * 10.0),
+        ];
+        let throttle = solve_allocation(
+            &columns,
+            &[1.0; 4],
+            force(Vec3::new(5.0, 0.0, -2.0)),
+            &[1.0; 4],
+        );
+
+        let expected = [0.5, 0.0, 0.0, 0.2];
+        for (throttle, expected) in throttle.iter().zip(expected) {
+            assert!(
+                (throttle - expected).abs() < 1e-2,
+                "{throttle} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn saturated_wrench_is_clamped() {
+        let columns = [force(Vec3::X * 10.0), force(Vec3::NEG_X * 10.0)];
+
+        let throttle = solve_allocation(&columns, &[1.0; 2], force(Vec3::X * 50.0), &[1.0; 2]);
+        assert!((throttle[0] - 1.0).abs() < 1e-4);
+        assert!(throttle[1].abs() < 1e-4);
+
+        let throttle = solve_allocation(&columns, &[1.0; 2], force(Vec3::X * 50.0), &[0.3, 1.0]);
+        assert!((throttle[0] - 0.3).abs() < 1e-4);
+        assert!(throttle.iter().all(|t| (0.0..=1.0).contains(t)));
+    }
+
+    #[test]
+    fn stuck_on_thruster_is_countered() {
+        let mut world = World::new();
+        let ship = world
+            .spawn((
+                ReadMassProperties::default(),
+                ControlAuthority::default(),
+                Thrusters::new(vec![
+                    Thruster {
+                        thrust: 10.0,
+                        failure: ThrusterFailure::StuckOn,
+                        ..Default::default()
+                    },
+                    Thruster {
+                        direction: Quat::from_rotation_y(PI),
+                        thrust: 20.0,
+                        ..Default::default()
+                    },
+                ]),
+            ))
+            .id();
+
+        world.run_system_once(allocate_thrust);
+
+        let throttle = &world.get::<Thrusters>(ship).unwrap().throttle;
+        assert!(throttle[0].abs() < 1e-4);
+        assert!((throttle[1] - 0.5).abs() < 1e-2, "{}", throttle[1]);
+    }
+
+    #[test]
+    fn group_commands_do_not_couple() {
+        let thruster = |offset, direction, group| Thruster {
+            offset,
+            direction,
+            thrust: 10.0,
+            group,
+            ..Default::default()
+        };
+
+        let mut world = World::new();
+        let mut thrusters = Thrusters::new(vec![
+            Thruster {
+                thrust: 20.0,
+                ..thruster(Vec3::X, Quat::IDENTITY, ThrusterGroup::BACKWARD)
+            },
+            thruster(Vec3::NEG_X, Quat::IDENTITY, ThrusterGroup::BACKWARD),
+            thruster(
+                Vec3::Z,
+                Quat::from_rotation_y(PI / 2.0),
+                ThrusterGroup::YROT,
+            ),
+            thruster(
+                Vec3::NEG_Z,
+                Quat::from_rotation_y(-PI / 2.0),
+                ThrusterGroup::YROT,
+            ),
+            thruster(
+                Vec3::Z,
+                Quat::from_rotation_y(-PI / 2.0),
+                ThrusterGroup::NYROT,
+            ),
+            thruster(
+                Vec3::NEG_Z,
+                Quat::from_rotation_y(PI / 2.0),
+                ThrusterGroup::NYROT,
+            ),
+        ]);
+        thrusters.groups_to_fire = ThrusterGroup::BACKWARD;
+        let ship = world
+            .spawn((
+                ReadMassProperties::default(),
+                ControlAuthority::default(),
+                thrusters,
+            ))
+            .id();
+
+        world.run_system_once(update_control_authority);
+        world.run_system_once(allocate_thrust);
+
+        let thrusters = world.get::<Thrusters>(ship).unwrap();
+        let delivered = thrusters.thrusters.iter().zip(&thrusters.throttle).fold(
+            Wrench::ZERO,
+            |delivered, (thruster, throttle)| {
+                delivered
+                    + Wrench::new(thruster.local_force(), thruster.local_torque(Vec3::ZERO))
+                        * *throttle
+            },
+        );
+
+        assert!(
+            (delivered.force - Vec3::Z * 30.0).length() < 0.5,
+            "{}",
+            delivered.force
+        );
+        assert!(delivered.torque.length() < 0.5, "{}", delivered.torque);
+    }
+}
@@ -0,0 +1,145 @@
+use super::thrusters::{Thruster, ThrusterGroup, Thrusters};
+use crate::entity_label;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Component, Clone, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThrusterClassifier {
+    pub assign_groups: bool,
+    /// Fraction of a thruster's thrust along an axis for it to translate along that axis.
+    pub force_threshold: f32,
+    /// Lever arm in meters around an axis for a thruster to rotate around that axis.
+    pub lever_arm_threshold: f32,
+    #[serde(skip)]
+    pending: bool,
+}
+
+impl Default for ThrusterClassifier {
+    fn default() -> Self {
+        Self {
+            assign_groups: false,
+            force_threshold: 0.25,
+            lever_arm_threshold: 0.1,
+            pending: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GroupMismatch {
+    pub index: usize,
+    pub assigned: ThrusterGroup,
+    pub derived: ThrusterGroup,
+}
+
+impl GroupMismatch {
+    pub fn contradicting(&self) -> ThrusterGroup {
+        self.assigned.difference(self.derived)
+    }
+
+    pub fn missing(&self) -> ThrusterGroup {
+        self.derived.difference(self.assigned)
+    }
+}
+
+impl ThrusterClassifier {
+    pub fn derive_group(&self, thruster: &Thruster, center_of_mass: Vec3) -> ThrusterGroup {
+        let mut group = ThrusterGroup::NONE;
+
+        if thruster.thrust <= 0.0 {
+            return group;
+        }
+
+        let force = thruster.local_force() / thruster.thrust;
+        let lever_arm = thruster.local_torque(center_of_mass) / thruster.thrust;
+
+        for axis in 0..3 {
+            if force[axis] > self.force_threshold {
+                group |= ThrusterGroup::positive_translation(axis);
+            } else if force[axis] < -self.force_threshold {
+                group |= ThrusterGroup::negative_translation(axis);
+            }
+
+            if lever_arm[axis] > self.lever_arm_threshold {
+                group |= ThrusterGroup::positive_rotation(axis);
+            } else if lever_arm[axis] < -self.lever_arm_threshold {
+                group |= ThrusterGroup::negative_rotation(axis);
+            }
+        }
+
+        group
+    }
+
+    pub fn validate(&self, thrusters: &[Thruster], center_of_mass: Vec3) -> Vec<GroupMismatch> {
+        thrusters
+            .iter()
+            .enumerate()
+            .filter_map(|(index, thruster)| {
+                let derived = self.derive_group(thruster, center_of_mass);
+                (derived != thruster.group).then_some(GroupMismatch {
+                    index,
+                    assigned: thruster.group,
+                    derived,
+                })
+            })
+            .collect()
+    }
+}
+
+pub fn classify_thrusters(
+    mut query: Query<(
+        Entity,
+        Option<&Name>,
+        &ReadMassProperties,
+        &mut ThrusterClassifier,
+        &mut Thrusters,
+    )>,
+) {
+    for (entity, name, mass_props, mut classifier, mut thrusters) in query.iter_mut() {
+        if thrusters.is_changed() {
+            classifier.pending = true;
+        }
+
+        if !classifier.pending || mass_props.get().mass <= 0.0 {
+            continue;
+        }
+
+        classifier.pending = false;
+
+        let center_of_mass = mass_props.get().local_center_of_mass;
+
+        if classifier.assign_groups {
+            for index in 0..thrusters.thrusters.len() {
+                let group = classifier.derive_group(&thrusters.thrusters[index], center_of_mass);
+                // Only write real changes, the write itself is picked up as a layout change
+                if thrusters.thrusters[index].group != group {
+                    thrusters.thrusters[index].group = group;
+                }
+            }
+            continue;
+        }
+
+        let ship = entity_label(entity, name);
+
+        for mismatch in classifier.validate(&thrusters.thrusters, center_of_mass) {
+            let contradicting = mismatch.contradicting();
+            if contradicting != ThrusterGroup::NONE {
+                warn!(
+                    "Thruster {} on {ship} is assigned to {contradicting} which its geometry does not give, derived groups are {}",
+                    mismatch.index, mismatch.derived
+                );
+            }
+
+            let missing = mismatch.missing();
+            if missing != ThrusterGroup::NONE {
+                info!(
+                    "Thruster {} on {ship} also contributes to {missing}",
+                    mismatch.index
+                );
+            }
+        }
+    }
+}
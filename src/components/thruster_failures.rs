@@ -0,0 +1,110 @@
+use super::thrusters::Thrusters;
+use crate::entity_label;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub enum ThrusterFailure {
+    #[default]
+    None,
+    StuckOn,
+    StuckOff,
+    Reduced(f32),
+}
+
+impl ThrusterFailure {
+    pub fn controllable_fraction(&self) -> f32 {
+        match *self {
+            ThrusterFailure::None => 1.0,
+            ThrusterFailure::StuckOn | ThrusterFailure::StuckOff => 0.0,
+            ThrusterFailure::Reduced(fraction) => fraction.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn delivered_fraction(&self) -> f32 {
+        match *self {
+            ThrusterFailure::None | ThrusterFailure::StuckOn => 1.0,
+            ThrusterFailure::StuckOff => 0.0,
+            ThrusterFailure::Reduced(fraction) => fraction.clamp(0.0, 1.0),
+        }
+    }
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct ThrusterFailureEvent {
+    pub ship: Entity,
+    pub thruster: usize,
+    pub failure: ThrusterFailure,
+}
+
+#[derive(Clone, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize)]
+pub struct ScheduledFailure {
+    pub time: f32,
+    pub thruster: usize,
+    pub failure: ThrusterFailure,
+}
+
+#[derive(Component, Clone, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize)]
+pub struct ScheduledFailures {
+    pub failures: Vec<ScheduledFailure>,
+    #[serde(skip)]
+    elapsed: f32,
+}
+
+impl ScheduledFailures {
+    pub fn reconfigure(&mut self, definition: Self) {
+        self.failures = definition.failures;
+    }
+}
+
+pub fn trigger_scheduled_failures(
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ScheduledFailures)>,
+    mut events: EventWriter<ThrusterFailureEvent>,
+) {
+    for (ship, mut schedule) in query.iter_mut() {
+        let previous = schedule.elapsed;
+        schedule.elapsed += time.delta_seconds();
+
+        for scheduled in &schedule.failures {
+            if scheduled.time >= previous && scheduled.time < schedule.elapsed {
+                events.send(ThrusterFailureEvent {
+                    ship,
+                    thruster: scheduled.thruster,
+                    failure: scheduled.failure,
+                });
+            }
+        }
+    }
+}
+
+pub fn apply_thruster_failures(
+    mut events: EventReader<ThrusterFailureEvent>,
+    mut query: Query<(Option<&Name>, &mut Thrusters)>,
+) {
+    for event in events.read() {
+        let Ok((name, mut thrusters)) = query.get_mut(event.ship) else {
+            continue;
+        };
+
+        let ship = entity_label(event.ship, name);
+
+        let Some(thruster) = thrusters.thrusters.get_mut(event.thruster) else {
+            warn!(
+                "Ignoring failure of thruster {} on {ship}, it only has {}",
+                event.thruster,
+                thrusters.thrusters.len()
+            );
+            continue;
+        };
+
+        thruster.failure = event.failure;
+        warn!(
+            "Thruster {} on {ship} is now {:?}",
+            event.thruster, event.failure
+        );
+    }
+}
@@ -1,9 +1,16 @@
+use super::{thrust_allocation::Wrench, thruster_failures::ThrusterFailure};
 use bevy::{math::vec3, prelude::*};
 use bevy_rapier3d::prelude::{ExternalForce, ReadMassProperties};
-use serde::{Deserialize, Serialize};
-use std::ops::{BitOr, BitOrAssign};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    ops::{BitOr, BitOrAssign},
+    str::FromStr,
+};
+use thiserror::Error;
 
-#[derive(Copy, Clone, Debug, Default, Reflect, Serialize, Deserialize)]
+/// Written by name, like `"LEFT|YROT"`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Reflect)]
 #[reflect(Serialize, Deserialize)]
 pub struct ThrusterGroup(u32);
 
@@ -22,13 +29,36 @@ impl ThrusterGroup {
     pub const ZROT: ThrusterGroup = ThrusterGroup(1 << 10);
     pub const NZROT: ThrusterGroup = ThrusterGroup(1 << 11);
 
+    pub const NAMES: [(&'static str, ThrusterGroup); 12] = [
+        ("FORWARD", ThrusterGroup::FORWARD),
+        ("BACKWARD", ThrusterGroup::BACKWARD),
+        ("LEFT", ThrusterGroup::LEFT),
+        ("RIGHT", ThrusterGroup::RIGHT),
+        ("UP", ThrusterGroup::UP),
+        ("DOWN", ThrusterGroup::DOWN),
+        ("XROT", ThrusterGroup::XROT),
+        ("NXROT", ThrusterGroup::NXROT),
+        ("YROT", ThrusterGroup::YROT),
+        ("NYROT", ThrusterGroup::NYROT),
+        ("ZROT", ThrusterGroup::ZROT),
+        ("NZROT", ThrusterGroup::NZROT),
+    ];
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
     pub fn intersects(self, other: ThrusterGroup) -> bool {
         (self.0 & other.0) != 0
     }
 
+    pub fn difference(self, other: ThrusterGroup) -> ThrusterGroup {
+        ThrusterGroup(self.0 & !other.0)
+    }
+
     pub fn index(self) -> usize {
         assert!(self.0 != 0);
-        self.0.trailing_zeros() as usize - 1
+        self.0.trailing_zeros() as usize
     }
 
     pub fn positive_rotation(axis: usize) -> ThrusterGroup {
@@ -48,6 +78,80 @@ impl ThrusterGroup {
             _ => panic!("Unknown Axis"),
         }
     }
+
+    pub fn positive_translation(axis: usize) -> ThrusterGroup {
+        match axis {
+            0 => ThrusterGroup::RIGHT,
+            1 => ThrusterGroup::UP,
+            2 => ThrusterGroup::BACKWARD,
+            _ => panic!("Unknown Axis"),
+        }
+    }
+
+    pub fn negative_translation(axis: usize) -> ThrusterGroup {
+        match axis {
+            0 => ThrusterGroup::LEFT,
+            1 => ThrusterGroup::DOWN,
+            2 => ThrusterGroup::FORWARD,
+            _ => panic!("Unknown Axis"),
+        }
+    }
+}
+
+impl fmt::Display for ThrusterGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == ThrusterGroup::NONE {
+            return write!(f, "NONE");
+        }
+
+        let names: Vec<&str> = ThrusterGroup::NAMES
+            .iter()
+            .filter(|(_, group)| self.intersects(*group))
+            .map(|(name, _)| *name)
+            .collect();
+
+        write!(f, "{}", names.join(" | "))
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown thruster group {0}")]
+pub struct ParseThrusterGroupError(String);
+
+impl FromStr for ThrusterGroup {
+    type Err = ParseThrusterGroupError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut group = ThrusterGroup::NONE;
+
+        if s.trim().eq_ignore_ascii_case("NONE") {
+            return Ok(group);
+        }
+
+        for name in s.split(['|', '+']).map(str::trim) {
+            let (_, named) = ThrusterGroup::NAMES
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .ok_or_else(|| ParseThrusterGroupError(name.to_string()))?;
+            group |= *named;
+        }
+
+        Ok(group)
+    }
+}
+
+impl Serialize for ThrusterGroup {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ThrusterGroup {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
 }
 
 impl BitOrAssign for ThrusterGroup {
@@ -64,31 +168,157 @@ impl BitOr for ThrusterGroup {
     }
 }
 
-#[derive(Reflect, Serialize, Deserialize, Default)]
+#[derive(Clone, Reflect, Serialize, Deserialize, Default)]
 #[reflect(Serialize, Deserialize)]
 pub struct Thruster {
     pub offset: Vec3,
     pub direction: Quat,
     pub thrust: f32,
     pub group: ThrusterGroup,
+    /// Seconds, how efficiently the thruster uses fuel. Zero burns no fuel at all.
+    #[serde(default)]
+    pub specific_impulse: f32,
+    /// Seconds for the output to close ~63% of the gap when throttling up.
+    #[serde(default)]
+    pub spool_up_time: f32,
+    /// Seconds for the output to close ~63% of the gap when throttling down.
+    #[serde(default)]
+    pub spool_down_time: f32,
+    /// Newton seconds, the smallest impulse the thruster delivers once lit.
+    #[serde(default)]
+    pub min_impulse_bit: f32,
+    #[serde(default)]
+    pub failure: ThrusterFailure,
+    #[serde(skip)]
+    pub output: f32,
+    #[serde(skip)]
+    pub pending_impulse: f32,
+}
+
+impl Thruster {
+    pub fn local_force(&self) -> Vec3 {
+        self.direction.mul_vec3(self.thrust * Vec3::Z)
+    }
+
+    pub fn local_torque(&self, center_of_mass: Vec3) -> Vec3 {
+        (self.offset - center_of_mass).cross(self.local_force())
+    }
+
+    pub fn delivered_thrust(&self) -> f32 {
+        self.failure.delivered_fraction() * self.thrust
+    }
+
+    pub fn spool(&mut self, throttle: f32, dt: f32) {
+        let mut throttle = match self.failure {
+            ThrusterFailure::StuckOn => 1.0,
+            ThrusterFailure::StuckOff => 0.0,
+            _ => throttle.clamp(0.0, 1.0),
+        };
+
+        if self.output <= 0.0 && throttle > 0.0 {
+            self.pending_impulse = self.min_impulse_bit;
+        }
+
+        if self.pending_impulse > 0.0 {
+            throttle = 1.0;
+        }
+
+        let time_constant = if throttle > self.output {
+            self.spool_up_time
+        } else {
+            self.spool_down_time
+        };
+
+        self.output = if time_constant > 0.0 {
+            self.output + (throttle - self.output) * (1.0 - (-dt / time_constant).exp())
+        } else {
+            throttle
+        };
+
+        if self.output < 0.001 && throttle == 0.0 {
+            self.output = 0.0;
+        }
+
+        self.pending_impulse =
+            (self.pending_impulse - self.output * self.delivered_thrust() * dt).max(0.0);
+    }
 }
 
+// Per-tick commands bypass change detection, so a changed `Thrusters` means its layout was edited
 #[derive(Component, Reflect, Serialize, Deserialize, Default)]
 #[reflect(Component, Serialize, Deserialize)]
 pub struct Thrusters {
     pub thrusters: Vec<Thruster>,
-    #[serde(skip_serializing)]
+    #[serde(skip)]
     pub group_thrust: [f32; 12],
-    #[serde(skip_serializing)]
+    #[serde(skip)]
     pub groups_to_fire: ThrusterGroup,
+    #[serde(skip)]
+    pub wrench: Wrench,
+    #[serde(skip)]
+    pub throttle: Vec<f32>,
+}
+
+impl Thrusters {
+    pub fn new(thrusters: Vec<Thruster>) -> Self {
+        Self {
+            thrusters,
+            ..Default::default()
+        }
+    }
+
+    pub fn reconfigure(&mut self, mut definition: Self) {
+        // Thrusters can be added, removed or reordered, so match them up by where they sit
+        for thruster in &mut definition.thrusters {
+            let Some(current) = self.thrusters.iter().find(|current| {
+                current.offset == thruster.offset && current.group == thruster.group
+            }) else {
+                continue;
+            };
+
+            thruster.failure = current.failure;
+            thruster.output = current.output;
+            thruster.pending_impulse = current.pending_impulse;
+        }
+        self.thrusters = definition.thrusters;
+    }
+
+    pub fn rotation_response_time(&self, center_of_mass: Vec3) -> f32 {
+        let (weighted, total) =
+            self.thrusters
+                .iter()
+                .fold((0.0, 0.0), |(weighted, total), thruster| {
+                    let torque = thruster.local_torque(center_of_mass).length();
+                    (weighted + torque * thruster.spool_up_time, total + torque)
+                });
+
+        if total > 0.0 {
+            weighted / total
+        } else {
+            0.0
+        }
+    }
 }
 
 pub fn reset_thrusters(mut query: Query<&mut Thrusters>) {
     for mut thrusters in query.iter_mut() {
+        let thrusters = thrusters.bypass_change_detection();
         thrusters.groups_to_fire = ThrusterGroup::NONE;
         for i in 0..12 {
             thrusters.group_thrust[i] = 0.0;
         }
+        thrusters.wrench = Wrench::ZERO;
+    }
+}
+
+pub fn spool_thrusters(time: Res<Time>, mut query: Query<&mut Thrusters>) {
+    for mut thrusters in query.iter_mut() {
+        let thrusters = thrusters.bypass_change_detection();
+        thrusters.throttle.resize(thrusters.thrusters.len(), 0.0);
+
+        for (thruster, &throttle) in thrusters.thrusters.iter_mut().zip(&thrusters.throttle) {
+            thruster.spool(throttle, time.delta_seconds());
+        }
     }
 }
 
@@ -99,42 +329,42 @@ pub fn thrusters(
         &mut ExternalForce,
         &ReadMassProperties,
     )>,
-    mut gizmos: Gizmos,
 ) {
     for (transform, thrusters, mut forces, mass_props) in query.iter_mut() {
         *forces = ExternalForce::default();
 
-        for thruster in thrusters
-            .thrusters
-            .iter()
-            .filter(|thruster| thruster.group.intersects(thrusters.groups_to_fire))
-        {
-            let mut magnitude = 0.0;
-            for i in 0..12 {
-                if thruster.group.0 & (1 << (i + 1)) > 0 {
-                    magnitude += thrusters.group_thrust[i];
-                }
-            }
+        let center_of_mass = transform.transform_point(mass_props.get().local_center_of_mass);
 
-            if magnitude == 0.0 {
-                magnitude = 1.0;
-            } else {
-                magnitude = magnitude.clamp(0.0, 1.0);
+        for thruster in &thrusters.thrusters {
+            if thruster.output <= 0.0 {
+                continue;
             }
 
             let pos = transform.transform_point(thruster.offset);
-            let center_of_mass = transform.transform_point(mass_props.get().local_center_of_mass);
-            let force = magnitude
-                * thruster.thrust
+            let force = thruster.output
+                * thruster.delivered_thrust()
                 * -(transform.rotation * thruster.direction).mul_vec3(-Vec3::Z);
 
             *forces += ExternalForce::at_point(force, pos, center_of_mass);
+        }
+    }
+}
 
-            gizmos.line(
-                pos,
-                pos + 0.4 * -(magnitude * force.normalize()),
-                Srgba::RED,
-            );
+pub fn draw_thrusters(
+    query: Query<(&Transform, &Thrusters, &ReadMassProperties)>,
+    mut gizmos: Gizmos,
+) {
+    for (transform, thrusters, mass_props) in query.iter() {
+        for thruster in &thrusters.thrusters {
+            let magnitude = thruster.output;
+            if magnitude <= 0.0 || thruster.delivered_thrust() <= 0.0 {
+                continue;
+            }
+
+            let pos = transform.transform_point(thruster.offset);
+            let direction = -(transform.rotation * thruster.direction).mul_vec3(-Vec3::Z);
+
+            gizmos.line(pos, pos + 0.4 * -(magnitude * direction), Srgba::RED);
         }
 
         {
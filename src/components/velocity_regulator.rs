@@ -0,0 +1,111 @@
+use super::{
+    control_authority::ControlAuthority,
+    thrusters::{ThrusterGroup, Thrusters},
+};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Component, Clone, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VelocityRegulator {
+    target: Vec3,
+    #[serde(skip)]
+    local_velocity: Vec3,
+    p_gain: f32,
+    enable: bool,
+}
+
+impl Default for VelocityRegulator {
+    fn default() -> Self {
+        Self {
+            target: Vec3::ZERO,
+            local_velocity: Vec3::ZERO,
+            p_gain: 2.0,
+            enable: false,
+        }
+    }
+}
+
+impl VelocityRegulator {
+    pub fn reconfigure(&mut self, definition: Self) {
+        self.p_gain = definition.p_gain;
+    }
+
+    pub fn update_target(&mut self, target: Vec3) {
+        self.target = target;
+    }
+
+    pub fn target(&self) -> Vec3 {
+        self.target
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enable
+    }
+
+    pub fn set_enabled(&mut self, enable: bool) {
+        self.enable = enable;
+    }
+}
+
+pub fn max_acceleration(
+    authority: &ControlAuthority,
+    group: ThrusterGroup,
+    axis: usize,
+    mass: f32,
+) -> f32 {
+    if mass <= 0.0 {
+        return 0.0;
+    }
+
+    authority.group_capacity[group.index()].force[axis].abs() / mass
+}
+
+pub fn velocity_regulator(
+    mut query: Query<(
+        &Transform,
+        &Velocity,
+        &ReadMassProperties,
+        &ControlAuthority,
+        &mut Thrusters,
+        &mut VelocityRegulator,
+    )>,
+) {
+    for (transform, vel, mass_props, authority, mut thrusters, mut regulator) in query.iter_mut() {
+        let thrusters = thrusters.bypass_change_detection();
+        let inverse_rotation = transform.rotation.inverse();
+        regulator.local_velocity = inverse_rotation.mul_vec3(vel.linvel);
+
+        if regulator.enable {
+            let mass = mass_props.get().mass;
+            let error = inverse_rotation.mul_vec3(regulator.target) - regulator.local_velocity;
+
+            let mut groups_to_fire = ThrusterGroup::NONE;
+
+            for axis in 0..3 {
+                if error[axis] == 0.0 {
+                    continue;
+                }
+
+                let group = if error[axis] > 0.0 {
+                    ThrusterGroup::positive_translation(axis)
+                } else {
+                    ThrusterGroup::negative_translation(axis)
+                };
+
+                let max_acceleration = max_acceleration(authority, group, axis, mass);
+                if max_acceleration <= 0.0 {
+                    continue;
+                }
+
+                groups_to_fire |= group;
+                thrusters.group_thrust[group.index()] =
+                    regulator.p_gain * error[axis].abs() / max_acceleration;
+            }
+
+            thrusters.groups_to_fire |= groups_to_fire;
+        }
+    }
+}
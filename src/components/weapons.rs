@@ -0,0 +1,379 @@
+use super::target::Targeting;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const PROJECTILE_RADIUS: f32 = 0.05;
+
+const TRACER_SECONDS: f32 = 0.02;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub enum ProjectileKind {
+    #[default]
+    Simulated,
+    Raycast,
+}
+
+#[derive(Clone, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hardpoint {
+    pub offset: Vec3,
+    pub direction: Quat,
+    /// Shots per second. At most one shot is fired per tick.
+    pub fire_rate: f32,
+    pub muzzle_velocity: f32,
+    pub projectile: ProjectileKind,
+    /// Kilograms, sets the recoil and how hard hits push.
+    pub projectile_mass: f32,
+    /// Seconds before a projectile that hit nothing is removed.
+    pub projectile_lifetime: f32,
+    #[serde(skip)]
+    pub cooldown: f32,
+}
+
+impl Default for Hardpoint {
+    fn default() -> Self {
+        Self {
+            offset: Vec3::ZERO,
+            direction: Quat::IDENTITY,
+            fire_rate: 5.0,
+            muzzle_velocity: 300.0,
+            projectile: ProjectileKind::Simulated,
+            projectile_mass: 0.05,
+            projectile_lifetime: 4.0,
+            cooldown: 0.0,
+        }
+    }
+}
+
+impl Hardpoint {
+    pub fn local_direction(&self) -> Vec3 {
+        self.direction.mul_vec3(Vec3::NEG_Z)
+    }
+
+    pub fn range(&self) -> f32 {
+        self.muzzle_velocity * self.projectile_lifetime
+    }
+}
+
+#[derive(Component, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize)]
+pub struct Weapons {
+    pub hardpoints: Vec<Hardpoint>,
+    #[serde(skip)]
+    pub trigger: bool,
+}
+
+impl Weapons {
+    pub fn reconfigure(&mut self, mut definition: Self) {
+        for (hardpoint, current) in definition.hardpoints.iter_mut().zip(&self.hardpoints) {
+            hardpoint.cooldown = current.cooldown;
+        }
+        self.hardpoints = definition.hardpoints;
+    }
+
+    pub fn projectile_speed(&self) -> Option<f32> {
+        if self.hardpoints.is_empty() {
+            return None;
+        }
+
+        let total: f32 = self
+            .hardpoints
+            .iter()
+            .map(|hardpoint| hardpoint.muzzle_velocity)
+            .sum();
+        Some(total / self.hardpoints.len() as f32)
+    }
+}
+
+#[derive(Component, Clone, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Gunner {
+    pub aim_tolerance: f32,
+}
+
+impl Default for Gunner {
+    fn default() -> Self {
+        Self {
+            aim_tolerance: 1.0_f32.to_radians(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Projectile {
+    pub shooter: Entity,
+    pub kind: ProjectileKind,
+    pub mass: f32,
+    pub lifetime: f32,
+    pub velocity: Vec3,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ProjectileHit {
+    pub projectile: Entity,
+    pub shooter: Entity,
+    pub hit: Entity,
+    pub point: Vec3,
+    pub velocity: Vec3,
+}
+
+#[derive(SystemParam)]
+pub struct ProjectileHooks<'w, 's> {
+    projectiles: Query<'w, 's, &'static Projectile>,
+}
+
+impl ProjectileHooks<'_, '_> {
+    fn hits_shooter(&self, collider: Entity, body: Option<Entity>) -> bool {
+        self.projectiles
+            .get(collider)
+            .is_ok_and(|projectile| body == Some(projectile.shooter))
+    }
+}
+
+impl BevyPhysicsHooks for ProjectileHooks<'_, '_> {
+    fn filter_contact_pair(&self, context: PairFilterContextView) -> Option<SolverFlags> {
+        if self.hits_shooter(context.collider1(), context.rigid_body2())
+            || self.hits_shooter(context.collider2(), context.rigid_body1())
+        {
+            None
+        } else {
+            Some(SolverFlags::COMPUTE_IMPULSES)
+        }
+    }
+}
+
+pub fn reset_weapons(mut query: Query<&mut Weapons>) {
+    for mut weapons in query.iter_mut() {
+        weapons.trigger = false;
+    }
+}
+
+pub fn lead_with_weapons(mut query: Query<(&Weapons, &mut Targeting)>) {
+    for (weapons, mut targeting) in query.iter_mut() {
+        let projectile_speed = weapons.projectile_speed();
+        if targeting.projectile_speed != projectile_speed {
+            targeting.projectile_speed = projectile_speed;
+        }
+    }
+}
+
+pub fn gunner(
+    mut query: Query<(&Transform, &Gunner, &Targeting, &mut Weapons)>,
+    targets: Query<&Transform>,
+) {
+    for (transform, gunner, targeting, mut weapons) in query.iter_mut() {
+        let Some(aim_point) = targeting.lead_point().or_else(|| {
+            let target = targeting.selected()?;
+            targets.get(target).ok().map(|target| target.translation)
+        }) else {
+            continue;
+        };
+
+        let aimed = weapons.hardpoints.iter().any(|hardpoint| {
+            let muzzle = transform.transform_point(hardpoint.offset);
+            let to_target = aim_point - muzzle;
+            to_target.length() <= hardpoint.range()
+                && (transform.rotation * hardpoint.local_direction()).angle_between(to_target)
+                    <= gunner.aim_tolerance
+        });
+
+        if aimed {
+            weapons.trigger = true;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn fire_weapons(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(
+        Entity,
+        &Transform,
+        &Velocity,
+        &ReadMassProperties,
+        &mut Weapons,
+        Option<&mut ExternalImpulse>,
+    )>,
+) {
+    let dt = time.delta_seconds();
+
+    for (entity, transform, velocity, mass_props, mut weapons, impulse) in query.iter_mut() {
+        let weapons = weapons.as_mut();
+        let center_of_mass = transform.transform_point(mass_props.get().local_center_of_mass);
+        let mut recoil = ExternalImpulse::default();
+        let mut fired = false;
+
+        for hardpoint in weapons.hardpoints.iter_mut() {
+            hardpoint.cooldown -= dt;
+            if !weapons.trigger || hardpoint.fire_rate <= 0.0 {
+                hardpoint.cooldown = hardpoint.cooldown.max(0.0);
+                continue;
+            }
+            if hardpoint.cooldown > 0.0 {
+                continue;
+            }
+            // Carrying over the time since it was ready keeps the rate from snapping to whole ticks
+            hardpoint.cooldown = hardpoint.cooldown.max(-dt) + 1.0 / hardpoint.fire_rate;
+            fired = true;
+
+            let muzzle = transform.transform_point(hardpoint.offset);
+            let rotation = transform.rotation * hardpoint.direction;
+            let launch = rotation.mul_vec3(Vec3::NEG_Z) * hardpoint.muzzle_velocity;
+            let projectile_velocity =
+                velocity.linear_velocity_at_point(muzzle, center_of_mass) + launch;
+
+            let mut projectile = commands.spawn((
+                Name::new("Projectile"),
+                TransformBundle::from_transform(
+                    Transform::from_translation(muzzle).with_rotation(rotation),
+                ),
+                Projectile {
+                    shooter: entity,
+                    kind: hardpoint.projectile,
+                    mass: hardpoint.projectile_mass,
+                    lifetime: hardpoint.projectile_lifetime,
+                    velocity: projectile_velocity,
+                },
+            ));
+
+            if hardpoint.projectile == ProjectileKind::Simulated {
+                projectile.insert((
+                    RigidBody::Dynamic,
+                    Collider::ball(PROJECTILE_RADIUS),
+                    ColliderMassProperties::Mass(hardpoint.projectile_mass),
+                    GravityScale(0.0),
+                    Velocity::linear(projectile_velocity),
+                    Ccd::enabled(),
+                    ActiveEvents::COLLISION_EVENTS,
+                    ActiveHooks::FILTER_CONTACT_PAIRS,
+                ));
+            }
+
+            recoil += ExternalImpulse::at_point(
+                -launch * hardpoint.projectile_mass,
+                muzzle,
+                center_of_mass,
+            );
+        }
+
+        if let Some(mut impulse) = impulse.filter(|_| fired) {
+            *impulse += recoil;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn move_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    context: Res<RapierContext>,
+    mut projectiles: Query<(Entity, &mut Transform, &mut Projectile)>,
+    mut bodies: Query<(&Transform, &ReadMassProperties, &mut ExternalImpulse), Without<Projectile>>,
+    mut hits: EventWriter<ProjectileHit>,
+) {
+    let dt = time.delta_seconds();
+
+    for (entity, mut transform, mut projectile) in projectiles.iter_mut() {
+        projectile.lifetime -= dt;
+        if projectile.lifetime <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        if projectile.kind != ProjectileKind::Raycast {
+            continue;
+        }
+
+        let origin = transform.translation;
+        let filter = QueryFilter::new()
+            .exclude_rigid_body(projectile.shooter)
+            .exclude_sensors();
+
+        let Some((collider, toi)) = context.cast_ray(origin, projectile.velocity, dt, true, filter)
+        else {
+            transform.translation += projectile.velocity * dt;
+            continue;
+        };
+
+        let point = origin + projectile.velocity * toi;
+        let body = context.collider_parent(collider).unwrap_or(collider);
+        if let Ok((body_transform, mass_props, mut impulse)) = bodies.get_mut(body) {
+            let center_of_mass =
+                body_transform.transform_point(mass_props.get().local_center_of_mass);
+            *impulse += ExternalImpulse::at_point(
+                projectile.velocity * projectile.mass,
+                point,
+                center_of_mass,
+            );
+        }
+
+        hits.send(ProjectileHit {
+            projectile: entity,
+            shooter: projectile.shooter,
+            hit: collider,
+            point,
+            velocity: projectile.velocity,
+        });
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn projectile_collisions(
+    mut commands: Commands,
+    context: Res<RapierContext>,
+    mut collisions: EventReader<CollisionEvent>,
+    projectiles: Query<(&Transform, &Projectile, &Velocity)>,
+    mut hits: EventWriter<ProjectileHit>,
+) {
+    let mut removed = Vec::new();
+
+    for collision in collisions.read() {
+        let CollisionEvent::Started(a, b, _) = *collision else {
+            continue;
+        };
+
+        for (entity, other) in [(a, b), (b, a)] {
+            if removed.contains(&entity) {
+                continue;
+            }
+
+            let Ok((transform, projectile, velocity)) = projectiles.get(entity) else {
+                continue;
+            };
+
+            if context.collider_parent(other).unwrap_or(other) == projectile.shooter {
+                continue;
+            }
+
+            hits.send(ProjectileHit {
+                projectile: entity,
+                shooter: projectile.shooter,
+                hit: other,
+                point: transform.translation,
+                velocity: velocity.linvel,
+            });
+
+            commands.entity(entity).despawn_recursive();
+            removed.push(entity);
+        }
+    }
+}
+
+pub fn draw_projectiles(
+    query: Query<(&Transform, &Projectile, Option<&Velocity>)>,
+    mut gizmos: Gizmos,
+) {
+    for (transform, projectile, velocity) in query.iter() {
+        let velocity = velocity.map_or(projectile.velocity, |velocity| velocity.linvel);
+        gizmos.line(
+            transform.translation,
+            transform.translation - velocity * TRACER_SECONDS,
+            Srgba::rgb(1.0, 0.8, 0.3),
+        );
+    }
+}
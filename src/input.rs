@@ -0,0 +1,535 @@
+use crate::components::{
+    control_authority::ControlAuthority,
+    thrust_allocation::Wrench,
+    thrusters::{ThrusterGroup, Thrusters},
+};
+use bevy::{
+    asset::ron::{self, error::SpannedError, ser::PrettyConfig},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, io, path::Path};
+use thiserror::Error;
+
+const GAMEPAD_AXES: [GamepadAxisType; 6] = [
+    GamepadAxisType::LeftStickX,
+    GamepadAxisType::LeftStickY,
+    GamepadAxisType::LeftZ,
+    GamepadAxisType::RightStickX,
+    GamepadAxisType::RightStickY,
+    GamepadAxisType::RightZ,
+];
+
+const REBIND_AXIS_THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub enum InputSource {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    GamepadButton(GamepadButtonType),
+    /// One half of a gamepad axis, `positive` picking which one.
+    GamepadAxis {
+        axis: GamepadAxisType,
+        positive: bool,
+    },
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Key(key) => write!(f, "{key:?}"),
+            InputSource::Mouse(button) => write!(f, "Mouse {button:?}"),
+            InputSource::GamepadButton(button) => write!(f, "Gamepad {button:?}"),
+            InputSource::GamepadAxis { axis, positive } => {
+                write!(f, "Gamepad {axis:?}{}", if *positive { "+" } else { "-" })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub enum InputAction {
+    Group(ThrusterGroup),
+    Force(Vec3),
+    Torque(Vec3),
+    Fire,
+    ToggleMouseAim,
+    CycleFlightAssist,
+    NextTarget,
+    PreviousTarget,
+    NearestTarget,
+    ReticleTarget,
+    ToggleTargetLock,
+    DumpFlightRecording,
+}
+
+impl fmt::Display for InputAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputAction::Group(group) => write!(f, "{group}"),
+            InputAction::Force(force) => write!(f, "Force {force}"),
+            InputAction::Torque(torque) => write!(f, "Torque {torque}"),
+            InputAction::Fire => write!(f, "Fire"),
+            InputAction::ToggleMouseAim => write!(f, "Toggle mouse aim"),
+            InputAction::CycleFlightAssist => write!(f, "Cycle flight assist"),
+            InputAction::NextTarget => write!(f, "Next target"),
+            InputAction::PreviousTarget => write!(f, "Previous target"),
+            InputAction::NearestTarget => write!(f, "Nearest target"),
+            InputAction::ReticleTarget => write!(f, "Target in reticle"),
+            InputAction::ToggleTargetLock => write!(f, "Toggle target lock"),
+            InputAction::DumpFlightRecording => write!(f, "Dump flight recording"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub struct InputBinding {
+    pub input: InputSource,
+    pub action: InputAction,
+}
+
+impl InputBinding {
+    pub fn new(input: InputSource, action: InputAction) -> Self {
+        Self { input, action }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum InputMapError {
+    #[error("Could not access input map: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not parse input map: {0}")]
+    Parse(#[from] SpannedError),
+    #[error("Could not write input map: {0}")]
+    Write(#[from] ron::Error),
+}
+
+#[derive(Resource, Clone, Debug, Reflect, Serialize, Deserialize)]
+#[reflect(Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputMap {
+    pub bindings: Vec<InputBinding>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let group = |input, group| InputBinding::new(input, InputAction::Group(group));
+        let key = InputSource::Key;
+        let mouse = InputSource::Mouse;
+        let button = InputSource::GamepadButton;
+        let axis = |axis, positive| InputSource::GamepadAxis { axis, positive };
+
+        Self {
+            bindings: vec![
+                group(key(KeyCode::KeyW), ThrusterGroup::FORWARD),
+                group(key(KeyCode::KeyS), ThrusterGroup::BACKWARD),
+                group(key(KeyCode::KeyD), ThrusterGroup::RIGHT),
+                group(key(KeyCode::KeyA), ThrusterGroup::LEFT),
+                group(key(KeyCode::Space), ThrusterGroup::UP),
+                group(key(KeyCode::ShiftLeft), ThrusterGroup::DOWN),
+                group(key(KeyCode::Numpad6), ThrusterGroup::NYROT),
+                group(key(KeyCode::Numpad4), ThrusterGroup::YROT),
+                group(key(KeyCode::Numpad8), ThrusterGroup::NXROT),
+                group(key(KeyCode::Numpad5), ThrusterGroup::XROT),
+                group(key(KeyCode::Numpad9), ThrusterGroup::NZROT),
+                group(key(KeyCode::Numpad7), ThrusterGroup::ZROT),
+                group(key(KeyCode::KeyL), ThrusterGroup::NYROT),
+                group(key(KeyCode::KeyJ), ThrusterGroup::YROT),
+                group(key(KeyCode::KeyI), ThrusterGroup::NXROT),
+                group(key(KeyCode::KeyK), ThrusterGroup::XROT),
+                group(key(KeyCode::KeyO), ThrusterGroup::NZROT),
+                group(key(KeyCode::KeyU), ThrusterGroup::ZROT),
+                group(
+                    axis(GamepadAxisType::LeftStickY, true),
+                    ThrusterGroup::FORWARD,
+                ),
+                group(
+                    axis(GamepadAxisType::LeftStickY, false),
+                    ThrusterGroup::BACKWARD,
+                ),
+                group(
+                    axis(GamepadAxisType::LeftStickX, true),
+                    ThrusterGroup::RIGHT,
+                ),
+                group(
+                    axis(GamepadAxisType::LeftStickX, false),
+                    ThrusterGroup::LEFT,
+                ),
+                group(button(GamepadButtonType::RightTrigger2), ThrusterGroup::UP),
+                group(button(GamepadButtonType::LeftTrigger2), ThrusterGroup::DOWN),
+                group(
+                    axis(GamepadAxisType::RightStickX, true),
+                    ThrusterGroup::NYROT,
+                ),
+                group(
+                    axis(GamepadAxisType::RightStickX, false),
+                    ThrusterGroup::YROT,
+                ),
+                group(
+                    axis(GamepadAxisType::RightStickY, true),
+                    ThrusterGroup::NXROT,
+                ),
+                group(
+                    axis(GamepadAxisType::RightStickY, false),
+                    ThrusterGroup::XROT,
+                ),
+                group(
+                    button(GamepadButtonType::RightTrigger),
+                    ThrusterGroup::NZROT,
+                ),
+                group(button(GamepadButtonType::LeftTrigger), ThrusterGroup::ZROT),
+                InputBinding::new(mouse(MouseButton::Left), InputAction::Fire),
+                InputBinding::new(button(GamepadButtonType::West), InputAction::Fire),
+                InputBinding::new(key(KeyCode::KeyM), InputAction::ToggleMouseAim),
+                InputBinding::new(key(KeyCode::KeyV), InputAction::CycleFlightAssist),
+                InputBinding::new(key(KeyCode::KeyT), InputAction::NextTarget),
+                InputBinding::new(key(KeyCode::KeyR), InputAction::PreviousTarget),
+                InputBinding::new(key(KeyCode::KeyN), InputAction::NearestTarget),
+                InputBinding::new(key(KeyCode::KeyG), InputAction::ReticleTarget),
+                InputBinding::new(key(KeyCode::KeyF), InputAction::ToggleTargetLock),
+                InputBinding::new(key(KeyCode::F9), InputAction::DumpFlightRecording),
+            ],
+        }
+    }
+}
+
+impl InputMap {
+    pub const PATH: &'static str = "input.ron";
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, InputMapError> {
+        Ok(ron::de::from_bytes(&fs::read(path)?)?)
+    }
+
+    pub fn load_or_default(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match Self::load(path) {
+            Ok(input_map) => input_map,
+            Err(InputMapError::Io(err)) if err.kind() == io::ErrorKind::NotFound => default(),
+            Err(err) => {
+                warn!("Using default input map, {}: {err}", path.display());
+                default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), InputMapError> {
+        fs::write(
+            path,
+            ron::ser::to_string_pretty(self, PrettyConfig::default())?,
+        )?;
+        Ok(())
+    }
+
+    pub fn action_value(&self, input: &InputState, action: InputAction) -> f32 {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.action == action)
+            .map(|binding| input.value(&binding.input))
+            .fold(0.0, f32::max)
+    }
+}
+
+#[derive(Default)]
+pub struct ActionPresses {
+    pressed: Vec<InputAction>,
+}
+
+impl ActionPresses {
+    pub fn just_pressed(
+        &mut self,
+        input_map: &InputMap,
+        input: &InputState,
+        action: InputAction,
+    ) -> bool {
+        let pressed = input_map.action_value(input, action) > 0.5;
+        let was_pressed = self.pressed.contains(&action);
+
+        if pressed && !was_pressed {
+            self.pressed.push(action);
+        } else if !pressed {
+            self.pressed.retain(|other| *other != action);
+        }
+
+        pressed && !was_pressed
+    }
+}
+
+#[derive(SystemParam)]
+pub struct InputState<'w> {
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+    gamepad_button_axes: Res<'w, Axis<GamepadButton>>,
+    gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl InputState<'_> {
+    pub fn value(&self, source: &InputSource) -> f32 {
+        match *source {
+            InputSource::Key(key) => self.keyboard.pressed(key) as u8 as f32,
+            InputSource::Mouse(button) => self.mouse.pressed(button) as u8 as f32,
+            InputSource::GamepadButton(button_type) => self
+                .gamepads
+                .iter()
+                .map(|gamepad| {
+                    let button = GamepadButton::new(gamepad, button_type);
+                    let pressed = self.gamepad_buttons.pressed(button) as u8 as f32;
+                    let analog = self.gamepad_button_axes.get(button).unwrap_or(0.0);
+                    pressed.max(analog)
+                })
+                .fold(0.0, f32::max),
+            InputSource::GamepadAxis { axis, positive } => self
+                .gamepads
+                .iter()
+                .filter_map(|gamepad| self.gamepad_axes.get(GamepadAxis::new(gamepad, axis)))
+                .map(|value| if positive { value } else { -value })
+                .fold(0.0, f32::max)
+                .min(1.0),
+        }
+    }
+
+    pub fn just_activated(&self) -> Option<InputSource> {
+        if let Some(key) = self.keyboard.get_just_pressed().next() {
+            return Some(InputSource::Key(*key));
+        }
+
+        if let Some(button) = self.mouse.get_just_pressed().next() {
+            return Some(InputSource::Mouse(*button));
+        }
+
+        if let Some(button) = self.gamepad_buttons.get_just_pressed().next() {
+            return Some(InputSource::GamepadButton(button.button_type));
+        }
+
+        self.gamepads.iter().find_map(|gamepad| {
+            GAMEPAD_AXES.iter().find_map(|&axis| {
+                let value = self.gamepad_axes.get(GamepadAxis::new(gamepad, axis))?;
+                (value.abs() > REBIND_AXIS_THRESHOLD).then_some(InputSource::GamepadAxis {
+                    axis,
+                    positive: value > 0.0,
+                })
+            })
+        })
+    }
+}
+
+fn scale_command(command: Vec3, positive: Vec3, negative: Vec3) -> Vec3 {
+    let command = command.clamp(Vec3::NEG_ONE, Vec3::ONE);
+    command.max(Vec3::ZERO) * positive + command.min(Vec3::ZERO) * negative
+}
+
+pub fn apply_input_map(
+    input_map: &InputMap,
+    input: &InputState,
+    authority: &ControlAuthority,
+    ignored: ThrusterGroup,
+    thrusters: &mut Thrusters,
+) {
+    let mut group_thrust = [0.0_f32; 12];
+    let mut force = Vec3::ZERO;
+    let mut torque = Vec3::ZERO;
+
+    for binding in &input_map.bindings {
+        let value = input.value(&binding.input);
+        if value <= 0.0 {
+            continue;
+        }
+
+        match binding.action {
+            InputAction::Group(group) => {
+                for (_, named) in ThrusterGroup::NAMES {
+                    if group.intersects(named) {
+                        let thrust = &mut group_thrust[named.index()];
+                        *thrust = thrust.max(value);
+                    }
+                }
+            }
+            InputAction::Force(direction) => force += direction * value,
+            InputAction::Torque(direction) => torque += direction * value,
+            _ => {}
+        }
+    }
+
+    for axis in 0..3 {
+        let rotation =
+            ThrusterGroup::positive_rotation(axis) | ThrusterGroup::negative_rotation(axis);
+        if ignored.intersects(rotation) {
+            torque[axis] = 0.0;
+        }
+    }
+
+    let mut wrench = Wrench::new(
+        scale_command(force, authority.positive_force, authority.negative_force),
+        scale_command(torque, authority.positive_torque, authority.negative_torque),
+    );
+
+    for (_, group) in ThrusterGroup::NAMES {
+        let thrust = group_thrust[group.index()];
+        if thrust > 0.0 && !ignored.intersects(group) {
+            wrench += authority.group_wrench(group, thrust.min(1.0));
+        }
+    }
+
+    thrusters.wrench += wrench;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_input_map, scale_command, ActionPresses, InputAction, InputBinding, InputMap,
+        InputSource,
+    };
+    use crate::{
+        components::{
+            control_authority::ControlAuthority,
+            thrust_allocation::Wrench,
+            thrusters::{ThrusterGroup, Thrusters},
+        },
+        input::InputState,
+    };
+    use bevy::{
+        ecs::system::RunSystemOnce,
+        input::{
+            gamepad::{GamepadConnection, GamepadConnectionEvent, GamepadInfo},
+            InputPlugin,
+        },
+        prelude::*,
+    };
+
+    const GAMEPAD: Gamepad = Gamepad { id: 0 };
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(InputPlugin);
+        app.world_mut().send_event(GamepadConnectionEvent::new(
+            GAMEPAD,
+            GamepadConnection::Connected(GamepadInfo {
+                name: "Test".to_string(),
+            }),
+        ));
+        app.update();
+        app
+    }
+
+    fn authority() -> ControlAuthority {
+        let mut group_capacity = [Wrench::ZERO; 12];
+        group_capacity[ThrusterGroup::FORWARD.index()] =
+            Wrench::new(Vec3::new(0.0, 0.5, -20.0), Vec3::new(0.0, 0.3, 0.0));
+
+        ControlAuthority {
+            positive_force: Vec3::splat(10.0),
+            negative_force: Vec3::splat(20.0),
+            positive_torque: Vec3::splat(1.0),
+            negative_torque: Vec3::splat(2.0),
+            group_capacity,
+        }
+    }
+
+    fn apply(app: &mut App, bindings: Vec<InputBinding>, ignored: ThrusterGroup) -> Thrusters {
+        let input_map = InputMap { bindings };
+        let authority = authority();
+        app.world_mut().run_system_once(move |input: InputState| {
+            let mut thrusters = Thrusters::default();
+            apply_input_map(&input_map, &input, &authority, ignored, &mut thrusters);
+            thrusters
+        })
+    }
+
+    #[test]
+    fn command_is_scaled_by_authority_in_each_direction() {
+        let scaled = scale_command(
+            Vec3::new(0.5, -0.5, 2.0),
+            Vec3::new(10.0, 10.0, 10.0),
+            Vec3::new(20.0, 20.0, 20.0),
+        );
+        assert_eq!(scaled, Vec3::new(5.0, -10.0, 10.0));
+    }
+
+    #[test]
+    fn analog_inputs_are_scaled() {
+        let mut app = app();
+        app.world_mut()
+            .resource_mut::<Axis<GamepadAxis>>()
+            .set(GamepadAxis::new(GAMEPAD, GamepadAxisType::LeftStickY), 0.4);
+
+        let stick = |positive| InputSource::GamepadAxis {
+            axis: GamepadAxisType::LeftStickY,
+            positive,
+        };
+        let thrusters = apply(
+            &mut app,
+            vec![
+                InputBinding::new(stick(true), InputAction::Group(ThrusterGroup::FORWARD)),
+                InputBinding::new(stick(true), InputAction::Force(Vec3::NEG_Z)),
+                InputBinding::new(stick(false), InputAction::Group(ThrusterGroup::BACKWARD)),
+            ],
+            ThrusterGroup::NONE,
+        );
+
+        assert!((thrusters.wrench.force - Vec3::new(0.0, 0.0, -16.0)).length() < 1e-5);
+        assert_eq!(thrusters.wrench.torque, Vec3::ZERO);
+    }
+
+    #[test]
+    fn ignored_groups_are_left_out() {
+        let mut app = app();
+        let mut keyboard = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keyboard.press(KeyCode::KeyJ);
+        keyboard.press(KeyCode::KeyW);
+
+        let key = InputSource::Key;
+        let thrusters = apply(
+            &mut app,
+            vec![
+                InputBinding::new(key(KeyCode::KeyJ), InputAction::Group(ThrusterGroup::YROT)),
+                InputBinding::new(
+                    key(KeyCode::KeyJ),
+                    InputAction::Torque(Vec3::new(1.0, 1.0, 0.0)),
+                ),
+                InputBinding::new(
+                    key(KeyCode::KeyW),
+                    InputAction::Group(ThrusterGroup::FORWARD),
+                ),
+            ],
+            ThrusterGroup::YROT | ThrusterGroup::NYROT,
+        );
+
+        assert_eq!(thrusters.wrench.force, Vec3::new(0.0, 0.0, -20.0));
+        assert_eq!(thrusters.wrench.torque, Vec3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn actions_are_just_pressed_once_per_press() {
+        let mut app = app();
+        let system = app.world_mut().register_system(
+            |input_map: Res<InputMap>, input: InputState, mut presses: Local<ActionPresses>| {
+                presses.just_pressed(&input_map, &input, InputAction::DumpFlightRecording)
+            },
+        );
+        app.insert_resource(InputMap::default());
+        let just_pressed = |app: &mut App| app.world_mut().run_system(system).unwrap();
+
+        assert!(!just_pressed(&mut app));
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::F9);
+        assert!(just_pressed(&mut app));
+        assert!(!just_pressed(&mut app));
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(KeyCode::F9);
+        assert!(!just_pressed(&mut app));
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::F9);
+        assert!(just_pressed(&mut app));
+    }
+}
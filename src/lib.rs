@@ -0,0 +1,175 @@
+use bevy::{prelude::*, scene::ScenePlugin, time::TimeUpdateStrategy};
+use bevy_rapier3d::prelude::*;
+use components::{
+    control_authority::{update_control_authority, ControlAuthority},
+    flight_assist::{flight_assist, FlightAssist, FlightAssistMode},
+    flight_recorder::{record_flight_data, FlightRecorder, TelemetryExport, TelemetryFormat},
+    fuel_tanks::{burn_fuel, update_fuel_mass, FuelTank, FuelTanks},
+    orientation_regulator::{orientation_regulator, OrientationRegulator, RegulatorMode},
+    position_regulator::{position_regulator, PositionRegulator},
+    target::{target_update_system, validate_targets, Targeting},
+    thrust_allocation::{allocate_thrust, Wrench},
+    thruster_classifier::{classify_thrusters, ThrusterClassifier},
+    thruster_failures::{
+        apply_thruster_failures, trigger_scheduled_failures, ScheduledFailure, ScheduledFailures,
+        ThrusterFailure, ThrusterFailureEvent,
+    },
+    thrusters::{reset_thrusters, spool_thrusters, thrusters, Thruster, ThrusterGroup, Thrusters},
+    velocity_regulator::{velocity_regulator, VelocityRegulator},
+    weapons::{
+        fire_weapons, gunner, lead_with_weapons, move_projectiles, projectile_collisions,
+        reset_weapons, Gunner, Hardpoint, ProjectileHit, ProjectileHooks, ProjectileKind, Weapons,
+    },
+};
+use std::time::Duration;
+use stress_test::StressTestPlugin;
+
+pub mod assets;
+pub mod components;
+pub mod input;
+pub mod stress_test;
+
+pub const SIMULATION_HZ: f64 = 60.0;
+
+#[derive(Resource, Default)]
+pub struct SimulationTick(pub u64);
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+pub fn entity_label(entity: Entity, name: Option<&Name>) -> String {
+    name.map_or_else(|| format!("{entity}"), |name| name.to_string())
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlightSet {
+    Prepare,
+    Input,
+    Control,
+    Actuate,
+}
+
+pub struct SpaceBattleSimPlugin;
+
+impl Plugin for SpaceBattleSimPlugin {
+    fn build(&self, app: &mut App) {
+        let dt = 1.0 / SIMULATION_HZ;
+
+        app.add_plugins(RapierPhysicsPlugin::<ProjectileHooks>::default().in_fixed_schedule())
+            .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .insert_resource(TimestepMode::Fixed {
+                dt: dt as f32,
+                substeps: 1,
+            })
+            .init_resource::<SimulationTick>()
+            .init_resource::<TelemetryExport>()
+            .add_event::<ThrusterFailureEvent>()
+            .add_event::<ProjectileHit>()
+            .configure_sets(
+                FixedUpdate,
+                (
+                    FlightSet::Prepare,
+                    FlightSet::Input,
+                    FlightSet::Control,
+                    FlightSet::Actuate,
+                )
+                    .chain()
+                    .before(PhysicsSet::SyncBackend),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_tick,
+                    (validate_targets, lead_with_weapons, target_update_system).chain(),
+                    (trigger_scheduled_failures, apply_thruster_failures).chain(),
+                    classify_thrusters,
+                    (reset_thrusters, reset_weapons, update_control_authority),
+                )
+                    .chain()
+                    .in_set(FlightSet::Prepare),
+            )
+            .add_systems(FixedUpdate, gunner.in_set(FlightSet::Input))
+            .add_systems(
+                FixedUpdate,
+                (
+                    flight_assist,
+                    (
+                        orientation_regulator,
+                        (position_regulator, velocity_regulator).chain(),
+                    ),
+                )
+                    .chain()
+                    .in_set(FlightSet::Control),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    allocate_thrust,
+                    spool_thrusters,
+                    burn_fuel,
+                    thrusters,
+                    update_fuel_mass,
+                    fire_weapons,
+                    (move_projectiles, projectile_collisions),
+                )
+                    .chain()
+                    .in_set(FlightSet::Actuate),
+            )
+            .add_systems(
+                FixedUpdate,
+                record_flight_data
+                    .after(FlightSet::Actuate)
+                    .before(PhysicsSet::SyncBackend),
+            )
+            .register_type::<ThrusterGroup>()
+            .register_type::<Thruster>()
+            .register_type::<Thrusters>()
+            .register_type::<Wrench>()
+            .register_type::<Hardpoint>()
+            .register_type::<ProjectileKind>()
+            .register_type::<Weapons>()
+            .register_type::<Gunner>()
+            .register_type::<ThrusterClassifier>()
+            .register_type::<ThrusterFailure>()
+            .register_type::<ScheduledFailure>()
+            .register_type::<ScheduledFailures>()
+            .register_type::<ControlAuthority>()
+            .register_type::<FuelTank>()
+            .register_type::<FuelTanks>()
+            .register_type::<OrientationRegulator>()
+            .register_type::<RegulatorMode>()
+            .register_type::<VelocityRegulator>()
+            .register_type::<FlightAssist>()
+            .register_type::<Targeting>()
+            .register_type::<FlightAssistMode>()
+            .register_type::<PositionRegulator>()
+            .register_type::<FlightRecorder>()
+            .register_type::<TelemetryFormat>()
+            .register_type::<TelemetryExport>()
+            .register_type::<ReadMassProperties>();
+    }
+}
+
+/// An app running the flight model without a window or GPU, where every `App::update` after
+/// the first advances exactly one fixed tick.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+    ))
+    // Rapier builds colliders from meshes, so it needs the asset type even when nothing renders
+    .init_asset::<Mesh>()
+    .add_plugins((SpaceBattleSimPlugin, StressTestPlugin))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / SIMULATION_HZ,
+    )));
+
+    app.finish();
+    app.cleanup();
+    app
+}
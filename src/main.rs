@@ -1,56 +1,96 @@
 use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, math::vec3, prelude::*};
 use bevy_editor_pls::{AddEditorWindow, EditorPlugin};
-use bevy_rapier3d::{prelude::*, render::RapierDebugRenderPlugin};
-use components::{
-    defer_collider_loader::{defer_collider_loader, DeferColliderLoader},
-    max_torque::{update_max_torque, MaxTorque},
-    orientation_regulator::{orientation_regulator, OrientationRegulator},
-    player_ship::{player_thrusters, PlayerShip},
-    target::{target_update_system, Target},
-    thrusters::{debug_thruster, reset_thrusters, thrusters, Thruster, ThrusterGroup, Thrusters},
+use bevy_rapier3d::{prelude::PhysicsSet, render::RapierDebugRenderPlugin};
+use space_battle::{
+    assets::{
+        collider_cache::ColliderCache,
+        ship_definition::{
+            apply_ship_definitions, spawn_ship, ShipDefinition, ShipDefinitionLoader,
+        },
+    },
+    components::{
+        defer_collider_loader::{
+            defer_collider_loader, ColliderLoadFailed, DeferColliderLoader, MeshColliderShape,
+        },
+        flight_assist::{cycle_flight_assist, FlightAssist},
+        flight_recorder::{dump_flight_data, FlightRecorder},
+        gltf_collider_loader::{gltf_collider_loader, GltfColliderLoader},
+        mouse_aim::{draw_aim_reticle, mouse_aim, toggle_mouse_aim, MouseAim},
+        player_ship::{player_thrusters, player_weapons, PlayerShip},
+        target::{draw_selected_targets, select_targets, Target, Targeting},
+        thrusters::{debug_thruster, draw_thrusters},
+        weapons::draw_projectiles,
+    },
+    input::InputMap,
+    stress_test::{StressTestBenchmark, StressTestConfig, StressTestPlugin},
+    FlightSet, SpaceBattleSimPlugin,
+};
+use ui::{
+    input_map_panel::InputMapPanel,
+    physics_debug_panel::{record_physics_profile, PhysicsProfile, PhysicsProfilingPanel},
+    regulator_plot_panel::RegulatorPlotPanel,
 };
-use std::f32::consts::PI;
-use ui::physics_debug_panel::PhysicsProfilingPanel;
 
-mod components;
 mod ui;
 
 fn main() {
-    App::new()
-        .insert_resource(Msaa::Sample4)
+    let mut app = App::new();
+
+    if std::env::args().any(|arg| arg == "--benchmark") {
+        app.insert_resource(StressTestBenchmark::default());
+    }
+
+    app.insert_resource(Msaa::Sample4)
         .add_plugins(DefaultPlugins)
         .add_plugins(EditorPlugin::default())
         .add_plugins(FrameTimeDiagnosticsPlugin)
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_plugins(SpaceBattleSimPlugin)
+        .add_plugins(StressTestPlugin)
+        .init_resource::<StressTestConfig>()
+        .insert_resource(InputMap::load_or_default(InputMap::PATH))
         .add_plugins(RapierDebugRenderPlugin {
             enabled: true,
             ..Default::default()
         })
+        .init_asset::<ShipDefinition>()
+        .init_asset_loader::<ShipDefinitionLoader>()
+        .add_event::<ColliderLoadFailed>()
+        .init_resource::<ColliderCache>()
+        .init_resource::<PhysicsProfile>()
         .add_systems(Startup, add_test_objects)
-        .add_systems(Startup, setup_physics)
+        .add_systems(
+            FixedUpdate,
+            (player_thrusters, player_weapons).in_set(FlightSet::Input),
+        )
+        .add_systems(
+            FixedUpdate,
+            record_physics_profile.after(PhysicsSet::Writeback),
+        )
         .add_systems(
             Update,
             (
-                (
-                    target_update_system,
-                    (reset_thrusters, update_max_torque),
-                    (player_thrusters, orientation_regulator),
-                    thrusters,
-                    debug_thruster,
-                )
-                    .chain(),
+                draw_thrusters,
+                debug_thruster,
+                draw_projectiles,
+                dump_flight_data,
+                cycle_flight_assist,
+                (select_targets, draw_selected_targets).chain(),
+                (toggle_mouse_aim, mouse_aim, draw_aim_reticle).chain(),
                 defer_collider_loader,
+                gltf_collider_loader,
+                apply_ship_definitions,
             ),
         )
-        .register_type::<ThrusterGroup>()
         .register_type::<PlayerShip>()
-        .register_type::<Thruster>()
-        .register_type::<Thrusters>()
-        .register_type::<MaxTorque>()
-        .register_type::<OrientationRegulator>()
-        .register_type::<ReadMassProperties>()
+        .register_type::<MouseAim>()
+        .register_type::<InputMap>()
         .register_type::<DeferColliderLoader>()
+        .register_type::<MeshColliderShape>()
+        .register_type::<GltfColliderLoader>()
+        .register_type::<ColliderCache>()
         .add_editor_window::<PhysicsProfilingPanel>()
+        .add_editor_window::<RegulatorPlotPanel>()
+        .add_editor_window::<InputMapPanel>()
         .run();
 }
 
@@ -60,148 +100,24 @@ fn add_test_objects(
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    commands
-        .spawn(PbrBundle {
-            mesh: asset_server.load("models/ship.glb#Mesh0/Primitive0"),
-            material: asset_server.load("models/ship.glb#Material0"),
+    spawn_ship(
+        &mut commands,
+        asset_server.load("ships/player.ship.ron"),
+        Transform::default(),
+    )
+    .insert(Name::new("Player"))
+    .insert(PlayerShip)
+    .insert(MouseAim::default())
+    .insert(FlightAssist::default())
+    .insert(Targeting::default())
+    .insert(FlightRecorder::default())
+    .with_children(|p| {
+        p.spawn(Camera3dBundle {
+            transform: Transform::from_translation(Vec3::new(0.0, 1.0, 8.0))
+                .looking_at(Vec3::default(), Vec3::Y),
             ..Default::default()
-        })
-        .insert(Name::new("Player"))
-        .insert(DeferColliderLoader)
-        .insert(RigidBody::Dynamic)
-        .insert(GravityScale(0.0))
-        .insert(AdditionalMassProperties::Mass(100.0))
-        .insert(ReadMassProperties::default())
-        .insert(ExternalForce::default())
-        .insert(Velocity::default())
-        .insert(Sleeping::disabled())
-        .insert(Thrusters {
-            thrusters: Vec::from([
-                Thruster {
-                    offset: Vec3::new(0.0, 0.0, 4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, PI),
-                    thrust: 600.0,
-                    group: ThrusterGroup::FORWARD,
-                },
-                Thruster {
-                    offset: Vec3::new(0.0, 0.0, -4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, 0.0),
-                    thrust: 150.0,
-                    group: ThrusterGroup::BACKWARD,
-                },
-                // Upper pointing to sides
-                Thruster {
-                    offset: Vec3::new(1.0, 1.0, -4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, -PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::LEFT | ThrusterGroup::YROT | ThrusterGroup::ZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(1.0, 1.0, 4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, -PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::LEFT | ThrusterGroup::NYROT | ThrusterGroup::ZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(-1.0, 1.0, -4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::RIGHT | ThrusterGroup::NYROT | ThrusterGroup::NZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(-1.0, 1.0, 4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::RIGHT | ThrusterGroup::YROT | ThrusterGroup::NZROT,
-                },
-                // Lower pointing to sides
-                Thruster {
-                    offset: Vec3::new(1.0, -1.0, -4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, -PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::LEFT | ThrusterGroup::YROT | ThrusterGroup::NZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(1.0, -1.0, 4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, -PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::LEFT | ThrusterGroup::NYROT | ThrusterGroup::NZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(-1.0, -1.0, -4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::RIGHT | ThrusterGroup::NYROT | ThrusterGroup::ZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(-1.0, -1.0, 4.0),
-                    direction: Quat::from_axis_angle(Vec3::Y, PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::RIGHT | ThrusterGroup::YROT | ThrusterGroup::ZROT,
-                },
-                // Upper pointing up
-                Thruster {
-                    offset: Vec3::new(1.0, 1.0, -4.0),
-                    direction: Quat::from_axis_angle(Vec3::X, PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::DOWN | ThrusterGroup::NXROT | ThrusterGroup::NZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(1.0, 1.0, 4.0),
-                    direction: Quat::from_axis_angle(Vec3::X, PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::DOWN | ThrusterGroup::XROT | ThrusterGroup::NZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(-1.0, 1.0, -4.0),
-                    direction: Quat::from_axis_angle(Vec3::X, PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::DOWN | ThrusterGroup::NXROT | ThrusterGroup::ZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(-1.0, 1.0, 4.0),
-                    direction: Quat::from_axis_angle(Vec3::X, PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::DOWN | ThrusterGroup::XROT | ThrusterGroup::ZROT,
-                },
-                // Lower pointing down
-                Thruster {
-                    offset: Vec3::new(1.0, -1.0, -4.0),
-                    direction: Quat::from_axis_angle(Vec3::X, -PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::UP | ThrusterGroup::XROT | ThrusterGroup::ZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(1.0, -1.0, 4.0),
-                    direction: Quat::from_axis_angle(Vec3::X, -PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::UP | ThrusterGroup::NXROT | ThrusterGroup::ZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(-1.0, -1.0, -4.0),
-                    direction: Quat::from_axis_angle(Vec3::X, -PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::UP | ThrusterGroup::XROT | ThrusterGroup::NZROT,
-                },
-                Thruster {
-                    offset: Vec3::new(-1.0, -1.0, 4.0),
-                    direction: Quat::from_axis_angle(Vec3::X, -PI / 2.0),
-                    thrust: 25.0,
-                    group: ThrusterGroup::UP | ThrusterGroup::NXROT | ThrusterGroup::NZROT,
-                },
-            ]),
-            ..Default::default()
-        })
-        .insert(PlayerShip)
-        .insert(MaxTorque::default())
-        .insert(OrientationRegulator::default())
-        .with_children(|p| {
-            p.spawn(Camera3dBundle {
-                transform: Transform::from_translation(Vec3::new(0.0, 1.0, 8.0))
-                    .looking_at(Vec3::default(), Vec3::Y),
-                ..Default::default()
-            });
         });
+    });
 
     commands.spawn(PointLightBundle {
         transform: Transform::from_translation(Vec3::new(0.0, 5.0, 5.0)),
@@ -233,57 +149,3 @@ fn add_test_objects(
         })
         .insert(Name::new("Background"));
 }
-
-pub fn setup_physics(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
-    let mesh = meshes.add(Mesh::from(Cuboid::new(2.0, 2.0, 2.0)));
-
-    let num = 8;
-    let rad = 1.0;
-
-    let shift = rad * 2.0 + rad;
-    let centerx = shift * (num / 2) as f32;
-    let centery = shift / 2.0;
-    let centerz = shift * (num / 2) as f32;
-
-    let mut offset = -(num as f32) * (rad * 2.0 + rad) * 0.5;
-    let mut color = 0;
-
-    for j in 0..8 {
-        for i in 0..num {
-            for k in 0..num {
-                let x = i as f32 * shift - centerx + offset;
-                let y = j as f32 * shift + centery - 15.0;
-                let z = k as f32 * shift - centerz + offset;
-                color += 1;
-
-                commands
-                    .spawn_empty()
-                    .insert(PbrBundle {
-                        mesh: mesh.clone(),
-                        material: materials.add(StandardMaterial {
-                            base_color: Color::hsl(
-                                color as f32 / (num * num * num) as f32 * 360.0,
-                                1.0,
-                                0.75,
-                            ),
-                            metallic: 0.5,
-                            perceptual_roughness: 0.5,
-                            ..Default::default()
-                        }),
-                        transform: Transform::from_xyz(x, y, z),
-                        ..Default::default()
-                    })
-                    .insert(RigidBody::Dynamic)
-                    .insert(GravityScale(0.0))
-                    .insert(AdditionalMassProperties::Mass(1.0))
-                    .insert(Collider::cuboid(1.0, 1.0, 1.0));
-            }
-        }
-
-        offset -= 0.05 * rad * (num as f32 - 1.0);
-    }
-}
@@ -0,0 +1,460 @@
+use crate::components::flight_recorder::TelemetryExport;
+use bevy::{
+    math::{vec2, vec3},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+};
+
+#[derive(Clone, Debug, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShapeMix {
+    pub cuboid: f32,
+    pub ball: f32,
+    pub capsule: f32,
+    pub convex: f32,
+}
+
+impl Default for ShapeMix {
+    fn default() -> Self {
+        Self {
+            cuboid: 1.0,
+            ball: 0.0,
+            capsule: 0.0,
+            convex: 0.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Shape {
+    Cuboid,
+    Ball,
+    Capsule,
+    Convex,
+}
+
+impl ShapeMix {
+    fn pick(&self, rng: &mut SplitMix64) -> Shape {
+        let weights = [
+            (Shape::Cuboid, self.cuboid.max(0.0)),
+            (Shape::Ball, self.ball.max(0.0)),
+            (Shape::Capsule, self.capsule.max(0.0)),
+            (Shape::Convex, self.convex.max(0.0)),
+        ];
+
+        let mut choice = rng.next_f32() * weights.iter().map(|(_, weight)| weight).sum::<f32>();
+        for (shape, weight) in weights {
+            if choice < weight {
+                return shape;
+            }
+            choice -= weight;
+        }
+
+        Shape::Cuboid
+    }
+}
+
+#[derive(Resource, Clone, Debug, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct StressTestConfig {
+    pub enable: bool,
+    pub count: UVec3,
+    pub shapes: ShapeMix,
+    /// Meters across each body.
+    pub size: f32,
+    /// Meters of gap between neighbouring bodies.
+    pub spacing: f32,
+    pub center: Vec3,
+    /// Smallest and largest mass of a body.
+    pub mass_range: Vec2,
+    /// Largest angular velocity a body starts with, in radians per second.
+    pub initial_spin: f32,
+    pub joints: bool,
+    pub seed: u64,
+}
+
+impl Default for StressTestConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            count: UVec3::splat(8),
+            shapes: ShapeMix::default(),
+            size: 2.0,
+            spacing: 1.0,
+            center: vec3(-13.5, -3.0, -13.5),
+            mass_range: vec2(1.0, 1.0),
+            initial_spin: 0.0,
+            joints: false,
+            seed: 0,
+        }
+    }
+}
+
+impl StressTestConfig {
+    pub fn body_count(&self) -> u32 {
+        if self.enable {
+            self.count.x * self.count.y * self.count.z
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct StressTestBody;
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, range: Vec2) -> f32 {
+        range.x + self.next_f32() * (range.y - range.x)
+    }
+
+    fn unit_vector(&mut self) -> Vec3 {
+        loop {
+            let v = vec3(self.next_f32(), self.next_f32(), self.next_f32()) * 2.0 - Vec3::ONE;
+            if v.length_squared() > 1e-4 && v.length_squared() <= 1.0 {
+                return v.normalize();
+            }
+        }
+    }
+}
+
+fn shape_mesh(shape: Shape, size: f32) -> Mesh {
+    let half = 0.5 * size;
+    match shape {
+        Shape::Cuboid => Cuboid::new(size, size, size).into(),
+        Shape::Ball => Sphere::new(half).into(),
+        Shape::Capsule => Capsule3d::new(0.5 * half, half).into(),
+        Shape::Convex => Sphere::new(half)
+            .mesh()
+            .ico(1)
+            .expect("one subdivision is always valid"),
+    }
+}
+
+fn shape_collider(shape: Shape, size: f32, mesh: &Mesh) -> Option<Collider> {
+    let half = 0.5 * size;
+    match shape {
+        Shape::Cuboid => Some(Collider::cuboid(half, half, half)),
+        Shape::Ball => Some(Collider::ball(half)),
+        Shape::Capsule => Some(Collider::capsule_y(0.5 * half, 0.5 * half)),
+        Shape::Convex => Collider::from_bevy_mesh(mesh, &ComputedColliderShape::ConvexHull),
+    }
+}
+
+pub fn spawn_stress_test(
+    mut commands: Commands,
+    config: Res<StressTestConfig>,
+    bodies: Query<Entity, With<StressTestBody>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+
+    for entity in bodies.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !config.enable {
+        return;
+    }
+
+    let mut rng = SplitMix64(config.seed);
+    let shift = config.size + config.spacing;
+    let count = config.count.as_vec3();
+    let origin = config.center - 0.5 * shift * (count - Vec3::ONE);
+    let total = config.body_count().max(1) as f32;
+
+    let shapes = [Shape::Cuboid, Shape::Ball, Shape::Capsule, Shape::Convex].map(|shape| {
+        let mesh = shape_mesh(shape, config.size);
+        let collider = shape_collider(shape, config.size, &mesh);
+        if collider.is_none() {
+            warn!("Could not build a {shape:?} collider for the stress test");
+        }
+        (shape, collider, meshes.add(mesh))
+    });
+
+    let mut index = 0;
+    for j in 0..config.count.y {
+        for k in 0..config.count.z {
+            let mut previous: Option<Entity> = None;
+
+            for i in 0..config.count.x {
+                let shape = config.shapes.pick(&mut rng);
+                let mass = rng.range(config.mass_range);
+                let spin = config.initial_spin * rng.next_f32() * rng.unit_vector();
+                let translation = origin + shift * vec3(i as f32, j as f32, k as f32);
+
+                let (_, collider, mesh) = shapes.iter().find(|(s, _, _)| *s == shape).unwrap();
+                let Some(collider) = collider.clone() else {
+                    previous = None;
+                    continue;
+                };
+
+                let mut body = commands.spawn((
+                    Name::new(format!("Stress test {i} {j} {k}")),
+                    StressTestBody,
+                    RigidBody::Dynamic,
+                    GravityScale(0.0),
+                    ColliderMassProperties::Mass(mass),
+                    Velocity::angular(spin),
+                    collider,
+                ));
+
+                match materials.as_mut() {
+                    Some(materials) => {
+                        body.insert(PbrBundle {
+                            mesh: mesh.clone(),
+                            material: materials.add(StandardMaterial {
+                                base_color: Color::hsl(index as f32 / total * 360.0, 1.0, 0.75),
+                                metallic: 0.5,
+                                perceptual_roughness: 0.5,
+                                ..Default::default()
+                            }),
+                            transform: Transform::from_translation(translation),
+                            ..Default::default()
+                        });
+                    }
+                    None => {
+                        body.insert(TransformBundle::from_transform(
+                            Transform::from_translation(translation),
+                        ));
+                    }
+                }
+
+                if let Some(previous) = previous.filter(|_| config.joints) {
+                    body.insert(ImpulseJoint::new(
+                        previous,
+                        SphericalJointBuilder::new()
+                            .local_anchor1(0.5 * shift * Vec3::X)
+                            .local_anchor2(-0.5 * shift * Vec3::X),
+                    ));
+                }
+
+                previous = Some(body.id());
+                index += 1;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchmarkResult {
+    pub config: StressTestConfig,
+    pub bodies: u32,
+    pub steps: u32,
+    pub avg_step_ms: f64,
+    pub max_step_ms: f64,
+}
+
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct StressTestBenchmark {
+    pub configs: Vec<StressTestConfig>,
+    pub warmup_steps: u32,
+    pub measured_steps: u32,
+    #[reflect(ignore)]
+    pub results: Vec<BenchmarkResult>,
+    #[reflect(ignore)]
+    current: Option<usize>,
+    #[reflect(ignore)]
+    step: u32,
+    #[reflect(ignore)]
+    total_ms: f64,
+    #[reflect(ignore)]
+    max_ms: f64,
+}
+
+impl Default for StressTestBenchmark {
+    fn default() -> Self {
+        let base = StressTestConfig::default();
+        let mixed = ShapeMix {
+            cuboid: 1.0,
+            ball: 1.0,
+            capsule: 1.0,
+            convex: 1.0,
+        };
+
+        Self::new(vec![
+            StressTestConfig {
+                count: UVec3::splat(4),
+                ..base.clone()
+            },
+            base.clone(),
+            StressTestConfig {
+                count: UVec3::splat(12),
+                ..base.clone()
+            },
+            StressTestConfig {
+                shapes: mixed.clone(),
+                initial_spin: 1.0,
+                ..base.clone()
+            },
+            StressTestConfig {
+                shapes: mixed,
+                initial_spin: 1.0,
+                joints: true,
+                ..base
+            },
+        ])
+    }
+}
+
+impl StressTestBenchmark {
+    pub fn new(configs: Vec<StressTestConfig>) -> Self {
+        Self {
+            configs,
+            warmup_steps: 60,
+            measured_steps: 300,
+            results: Vec::new(),
+            current: None,
+            step: 0,
+            total_ms: 0.0,
+            max_ms: 0.0,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.results.len() >= self.configs.len()
+    }
+
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(
+            writer,
+            "bodies,cuboid,ball,capsule,convex,initial_spin,joints,steps,avg_step_ms,max_step_ms"
+        )?;
+
+        for result in &self.results {
+            let shapes = &result.config.shapes;
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{}",
+                result.bodies,
+                shapes.cuboid,
+                shapes.ball,
+                shapes.capsule,
+                shapes.convex,
+                result.config.initial_spin,
+                result.config.joints,
+                result.steps,
+                result.avg_step_ms,
+                result.max_step_ms
+            )?;
+        }
+
+        writer.flush()
+    }
+}
+
+pub fn run_stress_test_benchmark(
+    mut commands: Commands,
+    mut benchmark: ResMut<StressTestBenchmark>,
+    context: Res<RapierContext>,
+    export: Option<Res<TelemetryExport>>,
+) {
+    if benchmark.finished() {
+        return;
+    }
+
+    let Some(current) = benchmark.current else {
+        benchmark.current = Some(0);
+        if let Some(first) = benchmark.configs.first() {
+            commands.insert_resource(first.clone());
+        }
+        return;
+    };
+
+    benchmark.step += 1;
+    if benchmark.step <= benchmark.warmup_steps {
+        return;
+    }
+
+    let step_ms = context.pipeline.counters.step_time();
+    benchmark.total_ms += step_ms;
+    benchmark.max_ms = benchmark.max_ms.max(step_ms);
+
+    let measured = benchmark.step - benchmark.warmup_steps;
+    if measured < benchmark.measured_steps {
+        return;
+    }
+
+    let result = BenchmarkResult {
+        config: benchmark.configs[current].clone(),
+        bodies: benchmark.configs[current].body_count(),
+        steps: measured,
+        avg_step_ms: benchmark.total_ms / measured as f64,
+        max_step_ms: benchmark.max_ms,
+    };
+    info!(
+        "Stress test {}/{}: {} bodies, {:.3}ms average step, {:.3}ms max",
+        current + 1,
+        benchmark.configs.len(),
+        result.bodies,
+        result.avg_step_ms,
+        result.max_step_ms
+    );
+    benchmark.results.push(result);
+
+    benchmark.step = 0;
+    benchmark.total_ms = 0.0;
+    benchmark.max_ms = 0.0;
+
+    if let Some(next) = benchmark.configs.get(current + 1) {
+        commands.insert_resource(next.clone());
+        benchmark.current = Some(current + 1);
+        return;
+    }
+
+    if let Some(export) = export {
+        let path = export.directory.join("stress-test-benchmark.csv");
+        let result = fs::create_dir_all(&export.directory)
+            .and_then(|()| fs::File::create(&path))
+            .and_then(|file| benchmark.write_csv(io::BufWriter::new(file)));
+
+        match result {
+            Ok(()) => info!("Wrote stress test benchmark to {}", path.display()),
+            Err(err) => error!("Could not write {}: {err}", path.display()),
+        }
+    }
+}
+
+pub struct StressTestPlugin;
+
+impl Plugin for StressTestPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            spawn_stress_test.run_if(resource_exists::<StressTestConfig>),
+        )
+        .add_systems(
+            FixedUpdate,
+            run_stress_test_benchmark
+                .run_if(resource_exists::<StressTestBenchmark>)
+                .after(PhysicsSet::Writeback),
+        )
+        .register_type::<ShapeMix>()
+        .register_type::<StressTestConfig>()
+        .register_type::<StressTestBenchmark>();
+    }
+}
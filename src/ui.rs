@@ -1 +1,4 @@
+pub mod input_map_panel;
 pub mod physics_debug_panel;
+pub mod plot;
+pub mod regulator_plot_panel;
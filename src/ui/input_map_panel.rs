@@ -0,0 +1,163 @@
+use bevy::{ecs::system::SystemState, prelude::*};
+use bevy_editor_pls::{
+    editor_window::{EditorWindow, EditorWindowContext},
+    egui,
+};
+use space_battle::{
+    components::thrusters::ThrusterGroup,
+    input::{InputAction, InputBinding, InputMap, InputSource, InputState},
+};
+
+#[derive(Default)]
+pub struct InputMapState {
+    rebinding: Option<usize>,
+    adding: Option<InputAction>,
+    new_action: usize,
+    status: Option<String>,
+}
+
+fn action_choices() -> Vec<InputAction> {
+    let mut choices: Vec<InputAction> = ThrusterGroup::NAMES
+        .iter()
+        .map(|&(_, group)| InputAction::Group(group))
+        .collect();
+
+    for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+        choices.extend([
+            InputAction::Force(axis),
+            InputAction::Force(-axis),
+            InputAction::Torque(axis),
+            InputAction::Torque(-axis),
+        ]);
+    }
+
+    choices.extend([
+        InputAction::Fire,
+        InputAction::ToggleMouseAim,
+        InputAction::CycleFlightAssist,
+        InputAction::NextTarget,
+        InputAction::PreviousTarget,
+        InputAction::NearestTarget,
+        InputAction::ReticleTarget,
+        InputAction::ToggleTargetLock,
+        InputAction::DumpFlightRecording,
+    ]);
+    choices
+}
+
+pub struct InputMapPanel;
+
+impl EditorWindow for InputMapPanel {
+    type State = InputMapState;
+    const NAME: &'static str = "Input Map";
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let Some(state) = cx.state_mut::<Self>() else {
+            return;
+        };
+
+        if state.rebinding.is_some() || state.adding.is_some() {
+            let mut input = SystemState::<InputState>::new(world);
+            match input.get(world).just_activated() {
+                Some(InputSource::Key(KeyCode::Escape)) => {
+                    state.rebinding = None;
+                    state.adding = None;
+                }
+                Some(source) => {
+                    let mut input_map = world.resource_mut::<InputMap>();
+                    if let Some(action) = state.adding.take() {
+                        input_map.bindings.push(InputBinding::new(source, action));
+                    } else if let Some(binding) = state
+                        .rebinding
+                        .take()
+                        .and_then(|index| input_map.bindings.get_mut(index))
+                    {
+                        binding.input = source;
+                    }
+                }
+                None => {}
+            }
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                let result = world.resource::<InputMap>().save(InputMap::PATH);
+                state.status = Some(match result {
+                    Ok(()) => format!("Wrote {}", InputMap::PATH),
+                    Err(err) => format!("Could not write {}: {err}", InputMap::PATH),
+                });
+            }
+            if ui.button("Reload").clicked() {
+                *world.resource_mut::<InputMap>() = InputMap::load_or_default(InputMap::PATH);
+                state.status = None;
+            }
+            if ui.button("Defaults").clicked() {
+                *world.resource_mut::<InputMap>() = InputMap::default();
+                state.status = None;
+            }
+        });
+
+        if let Some(status) = &state.status {
+            ui.label(status);
+        }
+
+        ui.horizontal(|ui| {
+            let choices = action_choices();
+            egui::ComboBox::from_id_source("new_binding_action").show_index(
+                ui,
+                &mut state.new_action,
+                choices.len(),
+                |i| choices[i].to_string(),
+            );
+
+            let text = if state.adding.is_some() {
+                "Press an input, Escape to cancel"
+            } else {
+                "Add binding"
+            };
+            if ui.button(text).clicked() {
+                state.adding = choices.get(state.new_action).copied();
+                state.rebinding = None;
+            }
+        });
+
+        let mut input_map = world.resource_mut::<InputMap>();
+        let mut remove = None;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("input_map")
+                .striped(true)
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for heading in ["Action", "Input", ""] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+
+                    for (i, binding) in input_map.bindings.iter().enumerate() {
+                        ui.label(binding.action.to_string());
+
+                        let text = if state.rebinding == Some(i) {
+                            "Press an input, Escape to cancel".to_string()
+                        } else {
+                            binding.input.to_string()
+                        };
+                        if ui.button(text).clicked() {
+                            state.rebinding = Some(i);
+                            state.adding = None;
+                        }
+
+                        if ui.small_button("Remove").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if let Some(i) = remove {
+            input_map.bindings.remove(i);
+            state.rebinding = None;
+        }
+    }
+}
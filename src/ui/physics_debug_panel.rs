@@ -1,68 +1,234 @@
-use bevy::prelude::World;
+use super::plot::sparkline;
+use bevy::prelude::{Res, ResMut, Resource, World};
 use bevy_editor_pls::{
     editor_window::{EditorWindow, EditorWindowContext},
-    egui,
+    egui::{self, vec2, Color32},
 };
 use bevy_rapier3d::prelude::RapierContext;
+use space_battle::{components::flight_recorder::TelemetryExport, SimulationTick};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+const SPIKE_FACTOR: f64 = 2.0;
+
+const SPIKE_FLOOR: f64 = 0.05;
+
+/// Counter names with how deep they are nested, in the order `read_counters` returns them.
+const COUNTERS: [(&str, usize); 14] = [
+    ("Total", 0),
+    ("Collision detection", 0),
+    ("Broad-phase", 1),
+    ("Narrow-phase", 1),
+    ("Island computation", 0),
+    ("Solver", 0),
+    ("Velocity assembly", 1),
+    ("Velocity resolution", 1),
+    ("Velocity integration", 1),
+    ("CCD", 0),
+    ("TOI computation", 1),
+    ("CCD broad-phase", 1),
+    ("CCD narrow-phase", 1),
+    ("CCD solver", 1),
+];
+
+fn read_counters(context: &RapierContext) -> [f64; COUNTERS.len()] {
+    let counters = &context.pipeline.counters;
+    [
+        counters.step_time(),
+        counters.collision_detection_time(),
+        counters.broad_phase_time(),
+        counters.narrow_phase_time(),
+        counters.island_construction_time(),
+        counters.solver_time(),
+        counters.solver.velocity_assembly_time.time(),
+        counters.velocity_resolution_time(),
+        counters.solver.velocity_update_time.time(),
+        counters.ccd_time(),
+        counters.ccd.toi_computation_time.time(),
+        counters.ccd.broad_phase_time.time(),
+        counters.ccd.narrow_phase_time.time(),
+        counters.ccd.solver_time.time(),
+    ]
+}
+
+struct ProfilingSample {
+    tick: u64,
+    ccd_substeps: usize,
+    times: [f64; COUNTERS.len()],
+}
+
+#[derive(Resource)]
+pub struct PhysicsProfile {
+    paused: bool,
+    history: usize,
+    samples: VecDeque<ProfilingSample>,
+}
+
+impl Default for PhysicsProfile {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            history: 600,
+            samples: VecDeque::new(),
+        }
+    }
+}
+
+pub fn record_physics_profile(
+    tick: Res<SimulationTick>,
+    context: Res<RapierContext>,
+    mut profile: ResMut<PhysicsProfile>,
+) {
+    if profile.paused {
+        return;
+    }
+
+    profile.samples.push_back(ProfilingSample {
+        tick: tick.0,
+        ccd_substeps: context.pipeline.counters.ccd.num_substeps,
+        times: read_counters(&context),
+    });
+
+    while profile.samples.len() > profile.history {
+        profile.samples.pop_front();
+    }
+}
+
+impl PhysicsProfile {
+    fn export_csv(&self, path: &Path) -> io::Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        let mut header = vec!["tick".to_string(), "ccd_substeps".to_string()];
+        header.extend(
+            COUNTERS
+                .iter()
+                .map(|(name, _)| format!("{}_ms", name.to_lowercase().replace([' ', '-'], "_"))),
+        );
+        writeln!(writer, "{}", header.join(","))?;
+
+        for sample in &self.samples {
+            let mut row = vec![sample.tick.to_string(), sample.ccd_substeps.to_string()];
+            row.extend(sample.times.iter().map(f64::to_string));
+            writeln!(writer, "{}", row.join(","))?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[derive(Default)]
+pub struct PhysicsProfilingState {
+    export_status: Option<String>,
+}
 
 pub struct PhysicsProfilingPanel;
 
 impl EditorWindow for PhysicsProfilingPanel {
-    type State = ();
+    type State = PhysicsProfilingState;
     const NAME: &'static str = "Physics Profiling";
 
-    fn ui(world: &mut World, _cx: EditorWindowContext, ui: &mut egui::Ui) {
-        let context = world.resource::<RapierContext>();
-        let counters = &context.pipeline.counters;
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let Some(state) = cx.state_mut::<Self>() else {
+            return;
+        };
 
-        ui.label(format!("Total: {:.2}ms", counters.step_time()));
-        ui.label(format!(
-            "Collision detection: {:.2}ms",
-            counters.collision_detection_time()
-        ));
-        ui.indent("Collision detection", |ui| {
-            ui.label(format!("Broad-phase: {:.2}ms", counters.broad_phase_time()));
-            ui.label(format!(
-                "Narrow-phase: {:.2}ms",
-                counters.narrow_phase_time()
-            ));
+        let tick = world.resource::<SimulationTick>().0;
+        let directory = world.resource::<TelemetryExport>().directory.clone();
+        let mut profile = world.resource_mut::<PhysicsProfile>();
+
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut profile.paused, "Pause");
+            if ui.button("Clear").clicked() {
+                profile.samples.clear();
+            }
+            ui.label("History");
+            ui.add(
+                egui::DragValue::new(&mut profile.history)
+                    .range(10..=10_000)
+                    .suffix(" steps"),
+            );
+
+            if ui.button("Export CSV").clicked() {
+                let path: PathBuf = directory.join(format!("physics-profile-{tick}.csv"));
+
+                state.export_status = Some(match profile.export_csv(&path) {
+                    Ok(()) => format!("Wrote {}", path.display()),
+                    Err(err) => format!("Could not write {}: {err}", path.display()),
+                });
+            }
         });
+
+        if let Some(status) = &state.export_status {
+            ui.label(status);
+        }
+
+        let Some(last) = profile.samples.back() else {
+            return;
+        };
+
         ui.label(format!(
-            "Island computation: {:.2}ms",
-            counters.island_construction_time()
+            "{} steps, CCD substeps: {}",
+            profile.samples.len(),
+            last.ccd_substeps
         ));
-        ui.label(format!("Solver: {:.2}ms", counters.solver_time()));
-        ui.indent("Solver", |ui| {
-            let solver = &counters.solver;
-            ui.label(format!(
-                "Velocity assembly: {:.2}ms",
-                solver.velocity_assembly_time.time()
-            ));
-            ui.label(format!(
-                "Velocity resolution: {:.2}ms",
-                counters.velocity_resolution_time()
-            ));
-            ui.label(format!(
-                "Velocity integration: {:.2}ms",
-                solver.velocity_update_time.time()
-            ));
-        });
-        ui.label(format!("CCD: {:.2}ms", counters.ccd_time()));
-        ui.indent("CCD", |ui| {
-            ui.label(format!("# of substeps: {}", counters.ccd.num_substeps));
-            ui.label(format!(
-                "TOI computation: {:.2}ms",
-                counters.ccd.toi_computation_time.time()
-            ));
-            ui.label(format!(
-                "Broad-phase: {:.2}ms",
-                counters.ccd.broad_phase_time.time()
-            ));
-            ui.label(format!(
-                "Narrow-phase: {:.2}ms",
-                counters.ccd.narrow_phase_time.time()
-            ));
-            ui.label(format!("Solver: {:.2}ms", counters.ccd.solver_time.time()));
-        });
+
+        egui::Grid::new("physics_profiling")
+            .striped(true)
+            .num_columns(6)
+            .show(ui, |ui| {
+                for heading in ["Counter", "Last", "Min", "Avg", "Max", "History"] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+
+                for (i, (name, depth)) in COUNTERS.iter().enumerate() {
+                    let values: Vec<f32> = profile
+                        .samples
+                        .iter()
+                        .map(|sample| sample.times[i] as f32)
+                        .collect();
+
+                    let min = profile
+                        .samples
+                        .iter()
+                        .map(|sample| sample.times[i])
+                        .fold(f64::INFINITY, f64::min);
+                    let max = profile
+                        .samples
+                        .iter()
+                        .map(|sample| sample.times[i])
+                        .fold(0.0, f64::max);
+                    let avg = profile
+                        .samples
+                        .iter()
+                        .map(|sample| sample.times[i])
+                        .sum::<f64>()
+                        / profile.samples.len() as f64;
+                    let spike = (SPIKE_FACTOR * avg).max(SPIKE_FLOOR);
+
+                    ui.label(format!("{}{name}", "    ".repeat(*depth)));
+
+                    let text = format!("{:.3}ms", last.times[i]);
+                    if last.times[i] > spike {
+                        ui.colored_label(Color32::RED, text);
+                    } else {
+                        ui.label(text);
+                    }
+
+                    ui.label(format!("{min:.3}"));
+                    ui.label(format!("{avg:.3}"));
+                    ui.label(format!("{max:.3}"));
+                    sparkline(ui, &values, spike as f32, vec2(160.0, 18.0));
+                    ui.end_row();
+                }
+            });
     }
 }
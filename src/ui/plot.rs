@@ -0,0 +1,140 @@
+use bevy_editor_pls::egui::{self, pos2, vec2, Align2, Color32, FontId, Pos2, Rect, Sense, Stroke};
+
+pub struct Series<'a> {
+    pub name: &'a str,
+    pub color: Color32,
+    pub points: Vec<(f32, f32)>,
+}
+
+pub fn plot(
+    ui: &mut egui::Ui,
+    title: &str,
+    series: &[Series],
+    start: f32,
+    end: f32,
+    height: f32,
+) -> egui::Response {
+    ui.horizontal_wrapped(|ui| {
+        ui.strong(title);
+        for series in series {
+            ui.colored_label(series.color, series.name);
+        }
+    });
+
+    let (response, painter) =
+        ui.allocate_painter(vec2(ui.available_width(), height), Sense::hover());
+    let rect = response.rect;
+
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    let visible = || {
+        series
+            .iter()
+            .flat_map(|series| &series.points)
+            .filter(|(x, _)| (start..=end).contains(x))
+            .map(|(_, y)| *y)
+    };
+
+    let mut min = visible().fold(f32::INFINITY, f32::min);
+    let mut max = visible().fold(f32::NEG_INFINITY, f32::max);
+    if !min.is_finite() || !max.is_finite() {
+        min = -1.0;
+        max = 1.0;
+    }
+    if max - min < 1e-3 {
+        min -= 0.5;
+        max += 0.5;
+    }
+    let padding = 0.05 * (max - min);
+    min -= padding;
+    max += padding;
+
+    let to_screen = |(x, y): (f32, f32)| -> Pos2 {
+        pos2(
+            rect.left() + (x - start) / (end - start).max(f32::EPSILON) * rect.width(),
+            rect.bottom() - (y - min) / (max - min) * rect.height(),
+        )
+    };
+
+    let grid = Stroke::new(1.0, ui.visuals().weak_text_color().gamma_multiply(0.3));
+    if min < 0.0 && max > 0.0 {
+        let y = to_screen((start, 0.0)).y;
+        painter.hline(rect.x_range(), y, grid);
+    }
+
+    let clip = painter.with_clip_rect(rect);
+    for series in series {
+        let points: Vec<Pos2> = series
+            .points
+            .iter()
+            .filter(|(x, _)| *x >= start)
+            .map(|&point| to_screen(point))
+            .collect();
+
+        if points.len() > 1 {
+            clip.add(egui::Shape::line(points, Stroke::new(1.5, series.color)));
+        }
+    }
+
+    let font = FontId::monospace(10.0);
+    let text_color = ui.visuals().weak_text_color();
+    let inner = Rect::from_min_max(rect.min + vec2(4.0, 2.0), rect.max - vec2(4.0, 2.0));
+    painter.text(
+        inner.left_top(),
+        Align2::LEFT_TOP,
+        format!("{max:.3}"),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        inner.left_bottom(),
+        Align2::LEFT_BOTTOM,
+        format!("{min:.3}"),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        inner.right_bottom(),
+        Align2::RIGHT_BOTTOM,
+        format!("{:.1}s", end - start),
+        font,
+        text_color,
+    );
+
+    response
+}
+
+pub fn sparkline(ui: &mut egui::Ui, values: &[f32], spike: f32, size: egui::Vec2) {
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let rect = response.rect;
+
+    painter.rect_filled(rect, 1.0, ui.visuals().extreme_bg_color);
+
+    let max = values.iter().copied().fold(f32::EPSILON, f32::max);
+    let step = rect.width() / (values.len().max(2) - 1) as f32;
+    let to_screen = |i: usize, value: f32| {
+        pos2(
+            rect.left() + i as f32 * step,
+            rect.bottom() - value / max * rect.height(),
+        )
+    };
+
+    let points: Vec<Pos2> = values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| to_screen(i, value))
+        .collect();
+
+    if points.len() > 1 {
+        painter.add(egui::Shape::line(
+            points,
+            Stroke::new(1.0, ui.visuals().text_color()),
+        ));
+    }
+
+    for (i, &value) in values.iter().enumerate() {
+        if value > spike {
+            painter.circle_filled(to_screen(i, value), 1.5, Color32::RED);
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use super::plot::{plot, Series};
+use bevy::prelude::*;
+use bevy_editor_pls::{
+    editor_window::{EditorWindow, EditorWindowContext},
+    egui::{self, Color32},
+};
+use bevy_rapier3d::prelude::ReadMassProperties;
+use space_battle::{
+    components::{
+        orientation_regulator::OrientationRegulator, thrust_allocation::Wrench,
+        thrusters::Thrusters,
+    },
+    entity_label,
+};
+use std::collections::VecDeque;
+
+const AXES: [(&str, Color32); 3] = [
+    ("x", Color32::from_rgb(230, 80, 80)),
+    ("y", Color32::from_rgb(80, 200, 80)),
+    ("z", Color32::from_rgb(80, 140, 240)),
+];
+
+const PLOT_HEIGHT: f32 = 120.0;
+
+struct RegulatorSample {
+    time: f32,
+    remaining_angle: Vec3,
+    target_angvel: Vec3,
+    local_angvel: Vec3,
+    commanded: Wrench,
+    delivered: Wrench,
+}
+
+pub struct RegulatorPlotState {
+    ship: Option<Entity>,
+    paused: bool,
+    history: f32,
+    window: f32,
+    samples: VecDeque<RegulatorSample>,
+}
+
+impl Default for RegulatorPlotState {
+    fn default() -> Self {
+        Self {
+            ship: None,
+            paused: false,
+            history: 30.0,
+            window: 10.0,
+            samples: VecDeque::new(),
+        }
+    }
+}
+
+impl RegulatorPlotState {
+    fn record(&mut self, world: &mut World) {
+        let Some(ship) = self.ship else {
+            return;
+        };
+
+        let time = world.resource::<Time>().elapsed_seconds();
+        if self
+            .samples
+            .back()
+            .is_some_and(|sample| sample.time >= time)
+        {
+            return;
+        }
+
+        let Some((transform, regulator, thrusters, mass_props)) = world
+            .query::<(
+                &Transform,
+                &OrientationRegulator,
+                &Thrusters,
+                &ReadMassProperties,
+            )>()
+            .get(world, ship)
+            .ok()
+        else {
+            return;
+        };
+
+        let remaining = transform.rotation.inverse() * regulator.target();
+
+        let center_of_mass = mass_props.get().local_center_of_mass;
+        let delivered = thrusters
+            .thrusters
+            .iter()
+            .fold(Wrench::ZERO, |delivered, thruster| {
+                let fraction = thruster.output * thruster.failure.delivered_fraction();
+                delivered
+                    + Wrench::new(
+                        thruster.local_force(),
+                        thruster.local_torque(center_of_mass),
+                    ) * fraction
+            });
+
+        self.samples.push_back(RegulatorSample {
+            time,
+            remaining_angle: Vec3::from(remaining.to_euler(EulerRot::XYZ)),
+            target_angvel: regulator.target_angvel(),
+            local_angvel: regulator.local_angvel(),
+            commanded: thrusters.wrench,
+            delivered,
+        });
+
+        while self
+            .samples
+            .front()
+            .is_some_and(|sample| sample.time < time - self.history)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn series(&self, value: impl Fn(&RegulatorSample) -> f32) -> Vec<(f32, f32)> {
+        self.samples
+            .iter()
+            .map(|sample| (sample.time, value(sample)))
+            .collect()
+    }
+}
+
+pub struct RegulatorPlotPanel;
+
+impl EditorWindow for RegulatorPlotPanel {
+    type State = RegulatorPlotState;
+    const NAME: &'static str = "Regulator Plots";
+
+    fn ui(world: &mut World, mut cx: EditorWindowContext, ui: &mut egui::Ui) {
+        let Some(state) = cx.state_mut::<Self>() else {
+            return;
+        };
+
+        let ships: Vec<(Entity, String)> = world
+            .query_filtered::<(Entity, Option<&Name>), With<OrientationRegulator>>()
+            .iter(world)
+            .map(|(entity, name)| {
+                let label = entity_label(entity, name);
+                (entity, label)
+            })
+            .collect();
+
+        if !state
+            .ship
+            .is_some_and(|ship| ships.iter().any(|(entity, _)| *entity == ship))
+        {
+            state.ship = ships.first().map(|(entity, _)| *entity);
+            state.samples.clear();
+        }
+
+        ui.horizontal(|ui| {
+            let selected = ships
+                .iter()
+                .find(|(entity, _)| Some(*entity) == state.ship)
+                .map_or("None", |(_, label)| label.as_str());
+
+            let previous = state.ship;
+            egui::ComboBox::from_label("Ship")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (entity, label) in &ships {
+                        ui.selectable_value(&mut state.ship, Some(*entity), label);
+                    }
+                });
+            if state.ship != previous {
+                state.samples.clear();
+            }
+
+            ui.toggle_value(&mut state.paused, "Pause");
+            if ui.button("Clear").clicked() {
+                state.samples.clear();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("History");
+            ui.add(
+                egui::DragValue::new(&mut state.history)
+                    .range(1.0..=300.0)
+                    .suffix("s"),
+            );
+            ui.label("Window");
+            ui.add(
+                egui::DragValue::new(&mut state.window)
+                    .range(0.5..=state.history)
+                    .speed(0.1)
+                    .suffix("s"),
+            );
+        });
+
+        if !state.paused {
+            state.record(world);
+        }
+
+        let end = state.samples.back().map_or(0.0, |sample| sample.time);
+        let start = end - state.window;
+
+        let remaining_angle: Vec<Series> = AXES
+            .iter()
+            .enumerate()
+            .map(|(axis, (name, color))| Series {
+                name,
+                color: *color,
+                points: state.series(|sample| sample.remaining_angle[axis]),
+            })
+            .collect();
+
+        let mut angular_velocity = Vec::new();
+        for (axis, (name, color)) in AXES.iter().enumerate() {
+            angular_velocity.push(Series {
+                name,
+                color: *color,
+                points: state.series(|sample| sample.local_angvel[axis]),
+            });
+        }
+        for (axis, (_, color)) in AXES.iter().enumerate() {
+            angular_velocity.push(Series {
+                name: ["target x", "target y", "target z"][axis],
+                color: color.gamma_multiply(0.5),
+                points: state.series(|sample| sample.target_angvel[axis]),
+            });
+        }
+
+        let wrench_series = |value: fn(&Wrench) -> Vec3| -> Vec<Series> {
+            let mut series = Vec::new();
+            for (axis, (name, color)) in AXES.iter().enumerate() {
+                series.push(Series {
+                    name,
+                    color: *color,
+                    points: state.series(|sample| value(&sample.delivered)[axis]),
+                });
+            }
+            for (axis, (_, color)) in AXES.iter().enumerate() {
+                series.push(Series {
+                    name: ["commanded x", "commanded y", "commanded z"][axis],
+                    color: color.gamma_multiply(0.5),
+                    points: state.series(|sample| value(&sample.commanded)[axis]),
+                });
+            }
+            series
+        };
+        let force = wrench_series(|wrench| wrench.force);
+        let torque = wrench_series(|wrench| wrench.torque);
+
+        let mut zoom = 0.0;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (title, series) in [
+                ("Remaining angle (rad)", &remaining_angle),
+                ("Angular velocity (rad/s)", &angular_velocity),
+                ("Force (N)", &force),
+                ("Torque (N m)", &torque),
+            ] {
+                let response = plot(ui, title, series, start, end, PLOT_HEIGHT);
+                if response.hovered() {
+                    zoom += ui.input(|input| input.smooth_scroll_delta.y);
+                }
+            }
+        });
+
+        if zoom != 0.0 {
+            state.window = (state.window * (-zoom * 0.005).exp()).clamp(0.5, state.history);
+        }
+    }
+}
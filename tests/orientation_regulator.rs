@@ -0,0 +1,141 @@
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use space_battle::{
+    assets::ship_definition::{spawn_headless_ship, ShipDefinition},
+    components::orientation_regulator::OrientationRegulator,
+    headless_app, SimulationTick, SIMULATION_HZ,
+};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+const SHIP: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/ships/player.ship.ron");
+
+/// Degrees of error within which the ship counts as on target.
+const SETTLED_ERROR: f32 = 1.0;
+
+/// Degrees the ship may swing back out after first reaching the target.
+const MAX_OVERSHOOT: f32 = 5.0;
+
+struct Response {
+    errors: Vec<f32>,
+}
+
+impl Response {
+    fn settling_time(&self) -> Option<f32> {
+        let last_outside = self
+            .errors
+            .iter()
+            .rposition(|&error| error > SETTLED_ERROR)
+            .map_or(0, |tick| tick + 1);
+
+        (last_outside < self.errors.len()).then(|| last_outside as f32 / SIMULATION_HZ as f32)
+    }
+
+    fn overshoot(&self) -> f32 {
+        let Some(first_inside) = self.errors.iter().position(|&error| error <= SETTLED_ERROR)
+        else {
+            return f32::INFINITY;
+        };
+
+        self.errors[first_inside..]
+            .iter()
+            .copied()
+            .fold(0.0, f32::max)
+    }
+
+    fn final_error(&self) -> f32 {
+        self.errors.last().copied().unwrap_or(f32::INFINITY)
+    }
+}
+
+fn simulate(target: Quat, seconds: f32) -> Response {
+    let definition = ShipDefinition::from_file(SHIP).expect("ship definition should load");
+
+    let mut app = headless_app();
+    let ship = app
+        .world_mut()
+        .run_system_once(move |mut commands: Commands| {
+            spawn_headless_ship(&mut commands, &definition, Transform::default()).id()
+        });
+
+    app.world_mut()
+        .get_mut::<OrientationRegulator>(ship)
+        .expect("ship should have an orientation regulator")
+        .update_target(target);
+
+    let ticks = (seconds * SIMULATION_HZ as f32) as u64;
+    let mut errors = Vec::new();
+
+    while app.world().resource::<SimulationTick>().0 < ticks {
+        let tick = app.world().resource::<SimulationTick>().0;
+        app.update();
+
+        if app.world().resource::<SimulationTick>().0 > tick {
+            let rotation = app.world().get::<Transform>(ship).unwrap().rotation;
+            errors.push(rotation.angle_between(target).to_degrees());
+        }
+    }
+
+    Response { errors }
+}
+
+fn assert_settles(target: Quat, max_settling_time: f32) {
+    let response = simulate(target, max_settling_time + 5.0);
+
+    let settling_time = response
+        .settling_time()
+        .unwrap_or_else(|| panic!("never settled, final error {}°", response.final_error()));
+    assert!(
+        settling_time <= max_settling_time,
+        "settled after {settling_time}s, expected at most {max_settling_time}s"
+    );
+
+    let overshoot = response.overshoot();
+    assert!(
+        overshoot <= MAX_OVERSHOOT,
+        "overshot by {overshoot}°, expected at most {MAX_OVERSHOOT}°"
+    );
+
+    let final_error = response.final_error();
+    assert!(
+        final_error <= SETTLED_ERROR,
+        "ended {final_error}° off target"
+    );
+}
+
+#[test]
+fn yaw_90_degrees() {
+    assert_settles(Quat::from_rotation_y(FRAC_PI_2), 10.0);
+}
+
+#[test]
+fn yaw_90_degrees_the_other_way() {
+    assert_settles(Quat::from_rotation_y(-FRAC_PI_2), 10.0);
+}
+
+#[test]
+fn flip_180_degrees() {
+    assert_settles(Quat::from_rotation_y(PI), 15.0);
+}
+
+#[test]
+fn pitch_and_roll() {
+    assert_settles(Quat::from_euler(EulerRot::XYZ, 0.6, 0.0, -1.1), 15.0);
+}
+
+#[test]
+fn arbitrary_orientations() {
+    for target in [
+        Quat::from_euler(EulerRot::XYZ, 0.7, -1.2, 0.4),
+        Quat::from_euler(EulerRot::XYZ, -2.0, 0.3, 1.5),
+        Quat::from_axis_angle(Vec3::new(1.0, 2.0, -0.5).normalize(), 2.5),
+    ] {
+        assert_settles(target, 15.0);
+    }
+}
+
+#[test]
+fn holds_current_orientation() {
+    let response = simulate(Quat::IDENTITY, 5.0);
+
+    let worst = response.errors.iter().copied().fold(0.0, f32::max);
+    assert!(worst <= SETTLED_ERROR, "drifted {worst}° off target");
+}
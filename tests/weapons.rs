@@ -0,0 +1,182 @@
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use bevy_rapier3d::prelude::*;
+use space_battle::{
+    assets::ship_definition::{spawn_headless_ship, ShipDefinition},
+    components::weapons::{Hardpoint, Projectile, ProjectileHit, ProjectileKind, Weapons},
+    headless_app, FlightSet, SimulationTick, SIMULATION_HZ,
+};
+
+const SHIP: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/ships/player.ship.ron");
+
+#[derive(Resource, Default)]
+struct Hits(Vec<ProjectileHit>);
+
+fn pull_trigger(mut query: Query<&mut Weapons>) {
+    for mut weapons in query.iter_mut() {
+        weapons.trigger = true;
+    }
+}
+
+fn record_hits(mut events: EventReader<ProjectileHit>, mut hits: ResMut<Hits>) {
+    hits.0.extend(events.read().copied());
+}
+
+fn armed_ship(hardpoint: Hardpoint) -> (App, Entity) {
+    let mut definition = ShipDefinition::from_file(SHIP).expect("ship definition should load");
+    definition.weapons = vec![hardpoint];
+
+    let mut app = headless_app();
+    app.init_resource::<Hits>().add_systems(
+        FixedUpdate,
+        (
+            pull_trigger.in_set(FlightSet::Input),
+            record_hits.after(FlightSet::Actuate),
+        ),
+    );
+
+    let ship = app
+        .world_mut()
+        .run_system_once(move |mut commands: Commands| {
+            spawn_headless_ship(&mut commands, &definition, Transform::default()).id()
+        });
+
+    (app, ship)
+}
+
+fn run_ticks(app: &mut App, ticks: u64) {
+    let end = app.world().resource::<SimulationTick>().0 + ticks;
+    while app.world().resource::<SimulationTick>().0 < end {
+        app.update();
+    }
+}
+
+fn projectiles(app: &mut App) -> Vec<(Entity, Vec3)> {
+    app.world_mut()
+        .query::<(Entity, &Projectile)>()
+        .iter(app.world())
+        .map(|(entity, projectile)| (entity, projectile.velocity))
+        .collect()
+}
+
+#[test]
+fn fires_at_the_fire_rate() {
+    let (mut app, _) = armed_ship(Hardpoint {
+        offset: Vec3::new(0.0, 0.0, -4.5),
+        fire_rate: 10.0,
+        ..Default::default()
+    });
+
+    run_ticks(&mut app, SIMULATION_HZ as u64);
+
+    let shots = projectiles(&mut app).len();
+    assert_eq!(
+        shots, 10,
+        "fired {shots} shots in a second at 10 per second"
+    );
+}
+
+#[test]
+fn projectiles_inherit_the_ship_velocity() {
+    let (mut app, ship) = armed_ship(Hardpoint {
+        offset: Vec3::new(0.0, 0.0, -4.5),
+        fire_rate: 0.1,
+        muzzle_velocity: 300.0,
+        ..Default::default()
+    });
+    app.world_mut().get_mut::<Velocity>(ship).unwrap().linvel = Vec3::new(10.0, 0.0, 0.0);
+
+    run_ticks(&mut app, 1);
+
+    let shots = projectiles(&mut app);
+    assert_eq!(shots.len(), 1);
+    let velocity = shots[0].1;
+    assert!(
+        (velocity - Vec3::new(10.0, 0.0, -300.0)).length() < 1e-3,
+        "projectile flies at {velocity}"
+    );
+}
+
+#[test]
+fn recoil_pushes_the_ship_back() {
+    let (mut app, ship) = armed_ship(Hardpoint {
+        offset: Vec3::new(0.0, 0.0, -4.5),
+        fire_rate: 0.1,
+        muzzle_velocity: 200.0,
+        projectile_mass: 5.0,
+        ..Default::default()
+    });
+
+    run_ticks(&mut app, 2);
+
+    let mass = app
+        .world()
+        .get::<ReadMassProperties>(ship)
+        .unwrap()
+        .get()
+        .mass;
+    let velocity = app.world().get::<Velocity>(ship).unwrap().linvel;
+    let expected = 200.0 * 5.0 / mass;
+    assert!(
+        (velocity.z - expected).abs() < 0.05 * expected,
+        "ship moves at {velocity} after firing, expected {expected} m/s backwards"
+    );
+}
+
+#[test]
+fn raycast_projectiles_hit_and_push_what_is_in_front() {
+    let (mut app, ship) = armed_ship(Hardpoint {
+        offset: Vec3::new(0.0, 0.0, -4.5),
+        fire_rate: 0.1,
+        muzzle_velocity: 400.0,
+        projectile: ProjectileKind::Raycast,
+        projectile_mass: 1.0,
+        ..Default::default()
+    });
+
+    let target = app
+        .world_mut()
+        .spawn((
+            TransformBundle::from_transform(Transform::from_xyz(0.0, 0.0, -50.0)),
+            RigidBody::Dynamic,
+            GravityScale(0.0),
+            Collider::cuboid(2.0, 2.0, 2.0),
+            ReadMassProperties::default(),
+            ExternalImpulse::default(),
+            Velocity::default(),
+        ))
+        .id();
+
+    run_ticks(&mut app, 20);
+
+    let hits = &app.world().resource::<Hits>().0;
+    assert_eq!(hits.len(), 1, "{} hits", hits.len());
+    assert_eq!(hits[0].shooter, ship);
+    assert_eq!(hits[0].hit, target);
+    assert!(
+        (hits[0].point.z - -48.0).abs() < 0.1,
+        "hit at {}",
+        hits[0].point
+    );
+
+    assert!(projectiles(&mut app).is_empty());
+    let velocity = app.world().get::<Velocity>(target).unwrap().linvel;
+    assert!(velocity.z < 0.0, "target moves at {velocity}");
+}
+
+#[test]
+fn simulated_projectiles_pass_through_the_shooter() {
+    let (mut app, ship) = armed_ship(Hardpoint {
+        offset: Vec3::ZERO,
+        fire_rate: 0.1,
+        ..Default::default()
+    });
+
+    run_ticks(&mut app, 10);
+
+    let hits = &app.world().resource::<Hits>().0;
+    assert!(
+        hits.iter().all(|hit| hit.hit != ship),
+        "projectile hit its own ship"
+    );
+    assert_eq!(projectiles(&mut app).len(), 1);
+}